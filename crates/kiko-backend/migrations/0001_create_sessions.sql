@@ -0,0 +1,8 @@
+-- Sessions are stored as a JSON document alongside the columns we query on.
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY NOT NULL,
+    started INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX sessions_started ON sessions (started);
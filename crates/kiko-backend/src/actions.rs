@@ -0,0 +1,343 @@
+//! Session actions shared by the WebSocket and REST APIs.
+//!
+//! Every action checks that the sender may perform it, applies the change
+//! atomically and publishes the updated session to all subscribers, so clients
+//! see the same [`SessionMessage::SessionUpdate`] whichever API made the change.
+//!
+//! The sender is the participant acting, or `None` for observers and anonymous
+//! REST callers, who are never allowed to change a session.
+
+use kiko::{
+    data::{
+        Participant, Session, SessionEndReason, SessionMessage, Story, ValidationError,
+        validate_participant_name, validate_story_title, validate_topic,
+    },
+    errors::{Report, WebSocketError},
+    id::{FacilitatorToken, ParticipantId, ResumeToken, SessionId, StoryId},
+    log,
+};
+
+use crate::services::{SessionCheck, SessionServiceError};
+
+impl From<SessionServiceError> for WebSocketError {
+    fn from(error: SessionServiceError) -> Self {
+        match error {
+            SessionServiceError::NotFound(session_id) => {
+                Self::SessionNotFound(session_id.to_string())
+            }
+            SessionServiceError::Rejected(report) => report
+                .downcast::<WebSocketError>()
+                .unwrap_or_else(|report| Self::InvalidMessage(report.to_string())),
+            SessionServiceError::Validation(violations) => match violations.into_iter().next() {
+                Some(violation) => Self::Invalid(violation),
+                None => Self::InvalidMessage("Invalid request".to_string()),
+            },
+            SessionServiceError::Conflict(message) => Self::InvalidMessage(message),
+            SessionServiceError::Storage(report) => {
+                log::error!("Session storage failed: {report:?}");
+                Self::StorageFailed
+            }
+        }
+    }
+}
+
+/// Ensures the sender is the session's facilitator.
+///
+/// Senders that have not joined as a participant are never privileged.
+fn ensure_facilitator(
+    session: &Session,
+    sender: Option<&ParticipantId>,
+) -> Result<(), WebSocketError> {
+    match sender {
+        Some(sender) if session.is_facilitator(sender) => Ok(()),
+        _ => Err(WebSocketError::NotFacilitator),
+    }
+}
+
+/// Ensures the sender may act on behalf of `target`.
+///
+/// Participants may always act for themselves, only the facilitator may act for others.
+fn ensure_can_act_for(
+    session: &Session,
+    sender: Option<&ParticipantId>,
+    target: &ParticipantId,
+) -> Result<(), WebSocketError> {
+    match sender {
+        None => Err(WebSocketError::NotJoined),
+        Some(sender) if sender == target || session.is_facilitator(sender) => Ok(()),
+        Some(_) => Err(WebSocketError::NotYourself),
+    }
+}
+
+/// Atomically applies `mutation` to a session and broadcasts the result to all subscribers.
+///
+/// Errors returned by `mutation` are passed through unchanged and nothing is stored.
+pub async fn modify_session<F>(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    mutation: F,
+) -> Result<Session, WebSocketError>
+where
+    F: FnOnce(&mut Session) -> Result<(), WebSocketError> + Send + 'static,
+{
+    let session = state
+        .sessions
+        .modify(
+            session_id,
+            Box::new(move |session| {
+                mutation(session).map_err(|error| SessionServiceError::Rejected(Report::new(error)))
+            }),
+        )
+        .await?;
+
+    // Broadcast the updated session to all subscribers
+    let update_message = SessionMessage::SessionUpdate(Box::new(session.clone()));
+    state
+        .pub_sub
+        .publish(session_id.clone(), update_message)
+        .await;
+
+    Ok(session)
+}
+
+/// Applies a change that only the facilitator may make.
+async fn modify_as_facilitator<F>(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    mutation: F,
+) -> Result<Session, WebSocketError>
+where
+    F: FnOnce(&mut Session) + Send + 'static,
+{
+    modify_session(state, session_id, move |session| {
+        ensure_facilitator(session, sender.as_ref())?;
+        mutation(session);
+        Ok(())
+    })
+    .await
+}
+
+/// Adds a new participant to a session, unless it already has the configured
+/// [`max_participants`](crate::config::SessionsConfig::max_participants).
+///
+/// Presenting the session's facilitator token makes them the facilitator, a wrong
+/// token is refused without joining. Returns the participant's ID and the resume
+/// token that proves their identity later.
+pub async fn join(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    participant_name: String,
+    facilitator_token: Option<FacilitatorToken>,
+) -> Result<(ParticipantId, ResumeToken), WebSocketError> {
+    let participant_name = validate_participant_name(&participant_name)?;
+    let participant_id = ParticipantId::new();
+    let participant = Participant::new(participant_id.clone(), participant_name);
+    let max = state.config.sessions.max_participants;
+    modify_session(state, session_id, move |session| {
+        if session.participants().len() >= max {
+            return Err(ValidationError::SessionFull { max }.into());
+        }
+        let participant_id = participant.id().clone();
+        session.add_participant(participant);
+        match facilitator_token {
+            Some(token) if !session.claim_facilitator(&participant_id, &token) => {
+                Err(WebSocketError::NotFacilitator)
+            }
+            _ => Ok(()),
+        }
+    })
+    .await?;
+
+    let resume_token = state.resume_tokens.issue(session_id, &participant_id);
+    Ok((participant_id, resume_token))
+}
+
+/// Removes a participant from a session, and invalidates their resume tokens.
+pub async fn leave(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    participant_id: ParticipantId,
+) -> Result<Session, WebSocketError> {
+    let session = modify_session(state, session_id, {
+        let participant_id = participant_id.clone();
+        move |session| {
+            ensure_can_act_for(session, sender.as_ref(), &participant_id)?;
+            session.remove_participant(&participant_id);
+            Ok(())
+        }
+    })
+    .await?;
+    state.resume_tokens.revoke_participant(&participant_id);
+
+    Ok(session)
+}
+
+/// Casts or withdraws a participant's vote, `None` withdraws it.
+pub async fn vote(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    participant_id: ParticipantId,
+    points: Option<String>,
+) -> Result<Session, WebSocketError> {
+    modify_session(state, session_id, move |session| {
+        ensure_can_act_for(session, sender.as_ref(), &participant_id)?;
+
+        // Only accept cards from the session's deck
+        if let Some(card) = &points
+            && !session.deck().contains(card)
+        {
+            return Err(WebSocketError::InvalidCard(card.clone()));
+        }
+
+        session.point(&participant_id, points);
+        Ok(())
+    })
+    .await
+}
+
+pub async fn set_topic(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    topic: String,
+) -> Result<Session, WebSocketError> {
+    let topic = validate_topic(&topic)?;
+    modify_as_facilitator(state, session_id, sender, move |session| {
+        session.set_topic(topic)
+    })
+    .await
+}
+
+pub async fn clear_points(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, Session::clear_points).await
+}
+
+pub async fn toggle_hide_points(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, Session::toggle_hide_points).await
+}
+
+/// Shows everyone's votes, unlike toggling this does nothing if they are already shown.
+pub async fn reveal_points(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, Session::reveal_points).await
+}
+
+/// Appends a story to the session's backlog.
+pub async fn add_story(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    title: String,
+) -> Result<Session, WebSocketError> {
+    let story = Story::new(StoryId::new(), validate_story_title(&title)?);
+    modify_as_facilitator(state, session_id, sender, move |session| {
+        session.add_story(story)
+    })
+    .await
+}
+
+pub async fn remove_story(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    story_id: StoryId,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, move |session| {
+        session.remove_story(&story_id)
+    })
+    .await
+}
+
+pub async fn reorder_story(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+    story_id: StoryId,
+    position: usize,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, move |session| {
+        session.move_story(&story_id, position)
+    })
+    .await
+}
+
+/// Makes the next story in the backlog the topic, an empty backlog changes nothing.
+pub async fn next_story(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+) -> Result<Session, WebSocketError> {
+    modify_as_facilitator(state, session_id, sender, |session| {
+        session.next_story();
+    })
+    .await
+}
+
+/// Ends a session on behalf of its facilitator.
+///
+/// The facilitator is checked under the same lock that removes the session, so
+/// the role can't change hands in between.
+pub async fn end(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    sender: Option<ParticipantId>,
+) -> Result<(), WebSocketError> {
+    state
+        .sessions
+        .end_if(
+            session_id,
+            Box::new(move |session| {
+                ensure_facilitator(session, sender.as_ref())
+                    .map_err(|error| SessionServiceError::Rejected(Report::new(error)))
+            }),
+        )
+        .await?;
+
+    announce_end(state, session_id, SessionEndReason::Closed).await;
+    Ok(())
+}
+
+/// Ends a session if it still passes `check`, and lets its subscribers know why.
+///
+/// The check runs under the storage's lock, so it sees the session as it is removed.
+pub async fn end_session(
+    state: &crate::AppState,
+    session_id: &SessionId,
+    reason: SessionEndReason,
+    check: SessionCheck<Session, SessionServiceError>,
+) -> Result<(), SessionServiceError> {
+    state.sessions.end_if(session_id, check).await?;
+
+    announce_end(state, session_id, reason).await;
+    Ok(())
+}
+
+/// Tells the subscribers of a removed session why it ended, and cleans up after it.
+///
+/// Connected clients are sent a [`SessionMessage::SessionEnded`] before the session's
+/// channel is cleaned up. Subscribers still receive already published events after
+/// cleanup, so nobody misses the message.
+async fn announce_end(state: &crate::AppState, session_id: &SessionId, reason: SessionEndReason) {
+    log::info!("Ended session {:?} ({:?})", session_id, reason);
+
+    state
+        .pub_sub
+        .publish(session_id.clone(), SessionMessage::SessionEnded(reason))
+        .await;
+    state.pub_sub.cleanup_session(session_id).await;
+    state.resume_tokens.revoke_session(session_id);
+}
@@ -0,0 +1,527 @@
+//! Backend configuration.
+//!
+//! Settings are layered: built-in defaults first, then an optional TOML file, then
+//! environment variables. The file is read from `KIKO_CONFIG`, or `kiko.toml` in
+//! the working directory if that exists. Every setting has a key in the file, and
+//! an environment variable that overrides it:
+//!
+//! | Key                                    | Environment variable              | Default         |
+//! |----------------------------------------|-----------------------------------|-----------------|
+//! | `bind`                                 | `KIKO_BIND`                       | `[::]:3030`     |
+//! | `port`                                 | `KIKO_BACKEND_PORT`               | port of `bind`  |
+//! | `cors_origins`                         | `KIKO_CORS_ORIGINS`               | local dev ports |
+//! | `log_filter`                           | `RUST_LOG`                        | see [`log`]     |
+//! | `storage.backend`                      | `KIKO_STORAGE`                    | `memory`        |
+//! | `storage.sqlite_path`                  | `KIKO_SQLITE_PATH`                | `kiko.db`       |
+//! | `sessions.resume_grace_period_secs`    | `KIKO_RESUME_GRACE_PERIOD_SECS`   | 60              |
+//! | `sessions.handshake_timeout_secs`      | `KIKO_HANDSHAKE_TIMEOUT_SECS`     | 10              |
+//! | `sessions.sweep_interval_secs`         | `KIKO_SWEEP_INTERVAL_SECS`        | 60              |
+//! | `sessions.empty_session_timeout_secs`  | `KIKO_EMPTY_SESSION_TIMEOUT_SECS` | 900             |
+//! | `sessions.max_participants`            | `KIKO_MAX_PARTICIPANTS`           | 50              |
+//! | `limits.session_creates_per_minute`    | `KIKO_SESSION_CREATES_PER_MINUTE` | 10              |
+//! | `limits.websocket_upgrades_per_minute` | `KIKO_WS_UPGRADES_PER_MINUTE`     | 30              |
+//! | `limits.messages_per_second`           | `KIKO_WS_MESSAGES_PER_SECOND`     | 10              |
+//! | `limits.message_burst`                 | `KIKO_WS_MESSAGE_BURST`           | 20              |
+//! | `limits.max_sockets_per_ip`            | `KIKO_WS_SOCKETS_PER_IP`          | 20              |
+//! | `limits.max_message_bytes`             | `KIKO_WS_MAX_MESSAGE_BYTES`       | 65536           |
+//!
+//! `port` replaces the port of `bind`, whichever of the file or the environment set
+//! either, so the listening port can be changed without repeating the address.
+//! `cors_origins` is a list in the file and comma separated in the environment,
+//! `["*"]` allows any origin. The result is validated before the server starts,
+//! so a typo fails loudly instead of silently falling back to a default.
+//!
+//! `sessions.max_participants` can only lower [`MAX_PARTICIPANTS`]. The lengths of
+//! names, topics and cards stay fixed, the frontend checks them before sending.
+
+use std::{
+    fmt::Display,
+    net::{Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use axum::http::HeaderValue;
+
+use kiko::data::MAX_PARTICIPANTS;
+use kiko::errors::Report;
+use kiko::log;
+use kiko::serde::{Deserialize, Deserializer};
+
+use crate::{limits::LimitsConfig, reaper, services::RESUME_GRACE_PERIOD};
+
+/// The config file used when `KIKO_CONFIG` isn't set, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "kiko.toml";
+
+/// Everything the backend can be configured with.
+///
+/// Deserialized from the config file, every setting it leaves out keeps its default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "kiko::serde", default, deny_unknown_fields)]
+pub struct Config {
+    /// Address the server listens on.
+    pub bind: SocketAddr,
+    /// Replaces the port of `bind`, applied once every source has been read.
+    pub port: Option<u16>,
+    /// Origins browsers may call the API from, `*` allows any.
+    pub cors_origins: Vec<String>,
+    /// Default log filter, in `RUST_LOG` syntax.
+    pub log_filter: String,
+    pub storage: StorageConfig,
+    pub sessions: SessionsConfig,
+    pub limits: LimitsConfig,
+}
+
+/// Where sessions are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(crate = "kiko::serde", rename_all = "lowercase")]
+pub enum StorageBackend {
+    Memory,
+    Sqlite,
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "memory" => Ok(Self::Memory),
+            "sqlite" => Ok(Self::Sqlite),
+            other => Err(format!("expected 'memory' or 'sqlite', got '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "kiko::serde", default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Database file used by the SQLite backend.
+    pub sqlite_path: PathBuf,
+}
+
+/// Timeouts for sessions and their connections, given in whole seconds in the file,
+/// and how many people may join one.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "kiko::serde", default, deny_unknown_fields)]
+pub struct SessionsConfig {
+    /// How long a disconnected participant can still be resumed.
+    #[serde(rename = "resume_grace_period_secs", deserialize_with = "secs")]
+    pub resume_grace_period: Duration,
+    /// How long a new WebSocket may take to send its `Hello`.
+    #[serde(rename = "handshake_timeout_secs", deserialize_with = "secs")]
+    pub handshake_timeout: Duration,
+    /// How often the reaper sweeps for finished sessions.
+    #[serde(rename = "sweep_interval_secs", deserialize_with = "secs")]
+    pub sweep_interval: Duration,
+    /// How long a session may sit without participants before it is ended.
+    #[serde(rename = "empty_session_timeout_secs", deserialize_with = "secs")]
+    pub empty_session_timeout: Duration,
+    /// Most participants a single session can have, at most [`MAX_PARTICIPANTS`].
+    pub max_participants: usize,
+}
+
+fn secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let dev_ports = [3000, 8000, 8080, 8081, 5173];
+        let cors_origins = dev_ports
+            .iter()
+            .flat_map(|port| {
+                [
+                    format!("http://localhost:{port}"),
+                    format!("http://127.0.0.1:{port}"),
+                ]
+            })
+            .collect();
+
+        Self {
+            bind: SocketAddr::from((Ipv6Addr::UNSPECIFIED, 3030)),
+            port: None,
+            cors_origins,
+            log_filter: log::DEFAULT_FILTER.to_string(),
+            storage: StorageConfig::default(),
+            sessions: SessionsConfig::default(),
+            limits: LimitsConfig::default(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            sqlite_path: PathBuf::from("kiko.db"),
+        }
+    }
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            resume_grace_period: RESUME_GRACE_PERIOD,
+            handshake_timeout: crate::handlers::v1::websocket::HANDSHAKE_TIMEOUT,
+            sweep_interval: reaper::SWEEP_INTERVAL,
+            empty_session_timeout: reaper::EMPTY_SESSION_TIMEOUT,
+            max_participants: MAX_PARTICIPANTS,
+        }
+    }
+}
+
+/// An environment variable that overrides a setting, parsed from its text.
+struct EnvOverride {
+    env: &'static str,
+    apply: fn(&mut Config, &str) -> Result<(), String>,
+}
+
+const ENV_OVERRIDES: &[EnvOverride] = &[
+    EnvOverride {
+        env: "KIKO_BIND",
+        apply: |config, value| set(&mut config.bind, value),
+    },
+    EnvOverride {
+        env: "KIKO_BACKEND_PORT",
+        apply: |config, value| {
+            config.port = Some(parse(value)?);
+            Ok(())
+        },
+    },
+    EnvOverride {
+        env: "KIKO_CORS_ORIGINS",
+        apply: |config, value| {
+            config.cors_origins = value
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+            Ok(())
+        },
+    },
+    EnvOverride {
+        env: "RUST_LOG",
+        apply: |config, value| set(&mut config.log_filter, value),
+    },
+    EnvOverride {
+        env: "KIKO_STORAGE",
+        apply: |config, value| set(&mut config.storage.backend, value),
+    },
+    EnvOverride {
+        env: "KIKO_SQLITE_PATH",
+        apply: |config, value| set(&mut config.storage.sqlite_path, value),
+    },
+    EnvOverride {
+        env: "KIKO_RESUME_GRACE_PERIOD_SECS",
+        apply: |config, value| set_secs(&mut config.sessions.resume_grace_period, value),
+    },
+    EnvOverride {
+        env: "KIKO_HANDSHAKE_TIMEOUT_SECS",
+        apply: |config, value| set_secs(&mut config.sessions.handshake_timeout, value),
+    },
+    EnvOverride {
+        env: "KIKO_SWEEP_INTERVAL_SECS",
+        apply: |config, value| set_secs(&mut config.sessions.sweep_interval, value),
+    },
+    EnvOverride {
+        env: "KIKO_EMPTY_SESSION_TIMEOUT_SECS",
+        apply: |config, value| set_secs(&mut config.sessions.empty_session_timeout, value),
+    },
+    EnvOverride {
+        env: "KIKO_MAX_PARTICIPANTS",
+        apply: |config, value| set(&mut config.sessions.max_participants, value),
+    },
+    EnvOverride {
+        env: "KIKO_SESSION_CREATES_PER_MINUTE",
+        apply: |config, value| set(&mut config.limits.session_creates_per_minute, value),
+    },
+    EnvOverride {
+        env: "KIKO_WS_UPGRADES_PER_MINUTE",
+        apply: |config, value| set(&mut config.limits.websocket_upgrades_per_minute, value),
+    },
+    EnvOverride {
+        env: "KIKO_WS_MESSAGES_PER_SECOND",
+        apply: |config, value| set(&mut config.limits.messages_per_second, value),
+    },
+    EnvOverride {
+        env: "KIKO_WS_MESSAGE_BURST",
+        apply: |config, value| set(&mut config.limits.message_burst, value),
+    },
+    EnvOverride {
+        env: "KIKO_WS_SOCKETS_PER_IP",
+        apply: |config, value| set(&mut config.limits.max_sockets_per_ip, value),
+    },
+    EnvOverride {
+        env: "KIKO_WS_MAX_MESSAGE_BYTES",
+        apply: |config, value| set(&mut config.limits.max_message_bytes, value),
+    },
+];
+
+fn parse<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid value '{value}': {e}"))
+}
+
+fn set<T>(target: &mut T, value: &str) -> Result<(), String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    *target = parse(value)?;
+    Ok(())
+}
+
+fn set_secs(target: &mut Duration, value: &str) -> Result<(), String> {
+    *target = Duration::from_secs(parse(value)?);
+    Ok(())
+}
+
+impl Config {
+    /// Loads the configuration from the config file and the process environment.
+    pub fn load() -> Result<Self, Report> {
+        let (path, required) = match std::env::var("KIKO_CONFIG") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+
+        let file = match std::fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(Report::msg(format!(
+                    "Failed to read config file {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        Self::from_sources(
+            file.as_deref().map(|contents| (path.as_path(), contents)),
+            |name| std::env::var(name).ok(),
+        )
+    }
+
+    /// Builds the configuration from a TOML file's contents and an environment lookup.
+    pub fn from_sources(
+        file: Option<(&Path, &str)>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Report> {
+        let mut problems = Vec::new();
+
+        let mut config = match file {
+            Some((path, contents)) => toml::from_str(contents).unwrap_or_else(|e| {
+                problems.push(format!("{}: {e}", path.display()));
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+
+        for setting in ENV_OVERRIDES {
+            if let Some(value) = env(setting.env)
+                && let Err(problem) = (setting.apply)(&mut config, &value)
+            {
+                problems.push(format!("{}: {problem}", setting.env));
+            }
+        }
+
+        if let Some(port) = config.port {
+            config.bind.set_port(port);
+        }
+
+        problems.extend(config.validate());
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(Report::msg(format!(
+                "Invalid configuration:\n  - {}",
+                problems.join("\n  - ")
+            )))
+        }
+    }
+
+    /// Checks the settings that parsed fine also make sense together.
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let any_origin = self.cors_origins.iter().any(|origin| origin == "*");
+        if any_origin && self.cors_origins.len() > 1 {
+            problems.push("cors_origins: '*' can't be combined with other origins".to_string());
+        }
+        for origin in self.cors_origins.iter().filter(|origin| *origin != "*") {
+            let is_http = origin.starts_with("http://") || origin.starts_with("https://");
+            if !is_http || origin.ends_with('/') || HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "cors_origins: '{origin}' is not an origin like 'https://kiko.example.com'"
+                ));
+            }
+        }
+
+        if self.storage.backend == StorageBackend::Sqlite
+            && self.storage.sqlite_path.as_os_str().is_empty()
+        {
+            problems.push("storage.sqlite_path: must not be empty".to_string());
+        }
+
+        let timeouts = [
+            (
+                "sessions.handshake_timeout_secs",
+                self.sessions.handshake_timeout,
+            ),
+            ("sessions.sweep_interval_secs", self.sessions.sweep_interval),
+        ];
+        for (key, timeout) in timeouts {
+            if timeout.is_zero() {
+                problems.push(format!("{key}: must be at least 1 second"));
+            }
+        }
+
+        if !(1..=MAX_PARTICIPANTS).contains(&self.sessions.max_participants) {
+            problems.push(format!(
+                "sessions.max_participants: must be between 1 and {MAX_PARTICIPANTS}"
+            ));
+        }
+
+        if self.limits.max_sockets_per_ip == 0 {
+            problems.push("limits.max_sockets_per_ip: must be at least 1".to_string());
+        }
+        if self.limits.max_message_bytes < 1024 {
+            problems.push("limits.max_message_bytes: must be at least 1024".to_string());
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_file(contents: &str) -> Result<Config, Report> {
+        Config::from_sources(Some((Path::new("kiko.toml"), contents)), |_| None)
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let config = Config::from_sources(None, |_| None).unwrap();
+        assert_eq!(config.bind.port(), 3030);
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        assert!(
+            config
+                .cors_origins
+                .contains(&"http://localhost:8080".to_string())
+        );
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let file = r#"
+            bind = "127.0.0.1:4000"
+            cors_origins = ["https://kiko.example.com"]
+
+            [storage]
+            backend = "sqlite"
+            sqlite_path = "/var/lib/kiko/kiko.db"
+
+            [sessions]
+            resume_grace_period_secs = 30
+            max_participants = 12
+
+            [limits]
+            max_sockets_per_ip = 5
+        "#;
+        let env = |name: &str| match name {
+            "KIKO_BACKEND_PORT" => Some("5000".to_string()),
+            "KIKO_WS_SOCKETS_PER_IP" => Some("8".to_string()),
+            _ => None,
+        };
+
+        let config = Config::from_sources(Some((Path::new("kiko.toml"), file)), env).unwrap();
+
+        assert_eq!(config.bind, "127.0.0.1:5000".parse().unwrap());
+        assert_eq!(config.cors_origins, vec!["https://kiko.example.com"]);
+        assert_eq!(config.storage.backend, StorageBackend::Sqlite);
+        assert_eq!(
+            config.storage.sqlite_path,
+            PathBuf::from("/var/lib/kiko/kiko.db")
+        );
+        assert_eq!(config.sessions.resume_grace_period, Duration::from_secs(30));
+        assert_eq!(config.sessions.max_participants, 12);
+        assert_eq!(config.limits.max_sockets_per_ip, 8);
+    }
+
+    #[test]
+    fn port_replaces_the_port_of_bind_wherever_either_is_set() {
+        let file = r#"
+            port = 5000
+            bind = "127.0.0.1:4000"
+        "#;
+        let config = from_file(file).unwrap();
+        assert_eq!(config.bind, "127.0.0.1:5000".parse().unwrap());
+
+        let env = |name: &str| (name == "KIKO_BIND").then(|| "10.0.0.1:4000".to_string());
+        let config =
+            Config::from_sources(Some((Path::new("kiko.toml"), "port = 5000")), env).unwrap();
+        assert_eq!(config.bind, "10.0.0.1:5000".parse().unwrap());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let file = r#"cors_origins = ["localhost:8080"]"#;
+        let env = |name: &str| match name {
+            "KIKO_STORAGE" => Some("postgres".to_string()),
+            "KIKO_WS_MESSAGES_PER_SECOND" => Some("0".to_string()),
+            "KIKO_MAX_PARTICIPANTS" => Some("500".to_string()),
+            _ => None,
+        };
+
+        let error = Config::from_sources(Some((Path::new("kiko.toml"), file)), env)
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            error.contains("'localhost:8080' is not an origin"),
+            "{error}"
+        );
+        assert!(error.contains("expected 'memory' or 'sqlite'"), "{error}");
+        assert!(error.contains("KIKO_WS_MESSAGES_PER_SECOND"), "{error}");
+        assert!(error.contains("sessions.max_participants"), "{error}");
+    }
+
+    #[test]
+    fn file_mistakes_point_at_the_setting() {
+        for (file, problem) in [
+            (r#"colour = "blue""#, "unknown field `colour`"),
+            (
+                "[storage]\nbackend = \"postgres\"",
+                "unknown variant `postgres`",
+            ),
+            (
+                "[limits]\nmessages_per_second = 0",
+                "messages_per_second = 0",
+            ),
+            (
+                "[sessions]\nsweep_interval_secs = \"soon\"",
+                "sweep_interval_secs",
+            ),
+        ] {
+            let error = from_file(file).unwrap_err().to_string();
+            assert!(error.contains(problem), "{error}");
+        }
+    }
+
+    #[test]
+    fn any_origin_stands_alone() {
+        assert!(from_file(r#"cors_origins = ["*"]"#).is_ok());
+        assert!(from_file(r#"cors_origins = ["*", "https://kiko.example.com"]"#).is_err());
+    }
+}
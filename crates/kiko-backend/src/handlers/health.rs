@@ -4,8 +4,6 @@ use axum::{Json, extract::State};
 use kiko::data::{HealthResponse, HealthStatus, ServiceInfo, UptimeInfo};
 use kiko::log;
 
-use crate::services::SessionService;
-
 fn uptime_seconds(started_at: chrono::DateTime<chrono::Utc>) -> i64 {
     (chrono::Utc::now() - started_at).num_seconds()
 }
@@ -35,6 +33,16 @@ fn service_uptime(started_at: chrono::DateTime<chrono::Utc>) -> (i64, String) {
     (seconds, human)
 }
 
+/// Reports whether the server is up, and how long it has been.
+#[utoipa::path(
+    get,
+    operation_id = "health",
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is healthy", body = HealthResponse),
+    )
+)]
 pub async fn get(State(state): State<Arc<crate::AppState>>) -> Json<HealthResponse> {
     let session_count = state.sessions.list().await.unwrap_or_default().len();
     let (seconds, human) = service_uptime(state.started_at);
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::{metrics::CONTENT_TYPE, services::SessionServiceError};
+
+/// Exposes the backend's metrics for Prometheus to scrape.
+pub async fn get(
+    State(state): State<Arc<crate::AppState>>,
+) -> Result<Response, SessionServiceError> {
+    let sessions = state.sessions.list().await?;
+    let body = state.metrics.encode(&sessions);
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{body::Body, http::Request};
+    use kiko::data::{CreateSession, Deck};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::metrics::METRICS_PATH;
+
+    async fn get(router: axum::Router, uri: &str) -> Response {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn metrics_are_exposed_for_prometheus() {
+        let state = Arc::new(crate::AppState::in_memory());
+        state
+            .sessions
+            .create(CreateSession {
+                name: "Planning".to_string(),
+                duration: Duration::from_secs(1800),
+                deck: Deck::default(),
+            })
+            .await
+            .unwrap();
+        let router = crate::setup_routes(state);
+
+        assert!(get(router.clone(), "/health").await.status().is_success());
+
+        let response = get(router, METRICS_PATH).await;
+        assert!(response.status().is_success());
+        assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let metrics = String::from_utf8(body.to_vec()).unwrap();
+        assert!(metrics.contains("kiko_sessions 1\n"));
+        assert!(metrics.contains("kiko_participants 0\n"));
+        assert!(metrics.contains(
+            r#"kiko_http_request_duration_seconds_count{method="GET",route="/health",status="200"} 1"#
+        ));
+    }
+}
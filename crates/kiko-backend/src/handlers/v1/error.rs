@@ -0,0 +1,90 @@
+//! JSON error responses shared by every REST handler.
+//!
+//! Errors are sent as an [`ErrorBody`] with the same [`ErrorCode`]s the WebSocket
+//! uses, so clients can handle both APIs the same way.
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use kiko::{
+    data::{ErrorBody, ErrorCode, ValidationError},
+    errors::WebSocketError,
+    log,
+};
+
+use crate::services::SessionServiceError;
+
+/// Builds an error response with a JSON [`ErrorBody`].
+pub fn error_response(status: StatusCode, code: ErrorCode, message: impl Into<String>) -> Response {
+    let body = ErrorBody {
+        code,
+        message: message.into(),
+        violations: Vec::new(),
+    };
+    (status, Json(body)).into_response()
+}
+
+/// Builds the response for a request that broke the validation rules.
+fn validation_response(message: String, violations: Vec<ValidationError>) -> Response {
+    let body = ErrorBody {
+        code: ErrorCode::ValidationFailed,
+        message,
+        violations,
+    };
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+/// The HTTP status matching an error code.
+pub fn status_for(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::SessionNotFound => StatusCode::NOT_FOUND,
+        ErrorCode::InvalidMessage | ErrorCode::InvalidCard | ErrorCode::ValidationFailed => {
+            StatusCode::BAD_REQUEST
+        }
+        ErrorCode::NotJoined | ErrorCode::ResumeRejected => StatusCode::UNAUTHORIZED,
+        ErrorCode::NotFacilitator | ErrorCode::NotYourself => StatusCode::FORBIDDEN,
+        ErrorCode::Conflict => StatusCode::CONFLICT,
+        ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::AlreadySubscribed
+        | ErrorCode::NotSubscribed
+        | ErrorCode::IncompatibleProtocol
+        | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Maps an action's error to an HTTP response.
+pub fn action_error(error: WebSocketError) -> Response {
+    if let WebSocketError::Invalid(violation) = error {
+        return validation_response(violation.to_string(), vec![violation]);
+    }
+
+    let code = error.code();
+    error_response(status_for(code), code, error.to_string())
+}
+
+impl IntoResponse for SessionServiceError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+        let code = match self {
+            SessionServiceError::NotFound(_) => ErrorCode::SessionNotFound,
+            SessionServiceError::Conflict(_) => ErrorCode::Conflict,
+            SessionServiceError::Validation(violations) => {
+                return validation_response(message, violations);
+            }
+            SessionServiceError::Rejected(_) => ErrorCode::ValidationFailed,
+            SessionServiceError::Storage(report) => {
+                // Storage details are for the logs, not for clients
+                log::error!("Session storage failed: {report:?}");
+                return error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorCode::Internal,
+                    "Failed to access session storage",
+                );
+            }
+        };
+        error_response(status_for(code), code, message)
+    }
+}
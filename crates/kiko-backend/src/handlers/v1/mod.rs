@@ -1,2 +1,4 @@
+pub mod error;
+pub mod openapi;
 pub mod session;
 pub mod websocket;
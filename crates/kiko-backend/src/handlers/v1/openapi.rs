@@ -0,0 +1,125 @@
+//! The OpenAPI document describing the REST API.
+//!
+//! Paths are collected from the handlers' `#[utoipa::path]` attributes as the
+//! routes are registered, see `setup_routes`, so the document can't drift from
+//! the router. This module only adds what the routes can't say themselves.
+
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+
+/// Where the document is served.
+pub const OPENAPI_PATH: &str = "/api/v1/openapi.json";
+
+/// Where the optional Swagger UI is served, with the `docs-ui` feature.
+pub const DOCS_PATH: &str = "/api/v1/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Kiko",
+        description = "Pointing poker sessions for agile teams. Live updates are sent over the WebSocket at `/api/v1/ws`."
+    ),
+    modifiers(&ResumeTokenAuth),
+    tags(
+        (name = "sessions", description = "Create, inspect and end sessions"),
+        (name = "participants", description = "Join and leave sessions"),
+        (name = "voting", description = "Run estimation rounds, changes are broadcast to WebSocket clients"),
+        (name = "websocket", description = "The live WebSocket API"),
+        (name = "health", description = "Server health"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Participants authenticate with the resume token they got when joining.
+struct ResumeTokenAuth;
+
+impl Modify for ResumeTokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "resume_token",
+            SecurityScheme::Http(
+                Http::builder()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "The resume token returned when joining the session in the path",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{body::Body, http::Request};
+    use kiko::serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Collects every `$ref` in the document.
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    found.push(reference);
+                }
+                object.values().for_each(|value| refs(value, found));
+            }
+            Value::Array(array) => array.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn document_covers_the_routes() {
+        let router = crate::setup_routes(Arc::new(crate::AppState::in_memory()));
+        let request = Request::get(OPENAPI_PATH).body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert!(response.status().is_success());
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let document: Value = kiko::serde_json::from_slice(&body).unwrap();
+
+        let paths = document["paths"].as_object().unwrap();
+        for path in [
+            "/health",
+            "/api/v1/session",
+            "/api/v1/session/{session_id}",
+            "/api/v1/session/{session_id}/participants",
+            "/api/v1/session/{session_id}/votes/{participant_id}",
+            "/api/v1/ws",
+        ] {
+            assert!(paths.contains_key(path), "{path} is not documented");
+        }
+        assert!(paths["/api/v1/session"]["get"].is_object());
+        assert!(paths["/api/v1/session"]["post"].is_object());
+
+        // Operation IDs name the generated client's methods, so they must be unique
+        let mut operation_ids: Vec<&str> = paths
+            .values()
+            .flat_map(|item| item.as_object().unwrap().values())
+            .filter_map(|operation| operation["operationId"].as_str())
+            .collect();
+        let operations = operation_ids.len();
+        operation_ids.sort();
+        operation_ids.dedup();
+        assert_eq!(operation_ids.len(), operations);
+
+        // Every referenced schema has to be in the document
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        let mut found = Vec::new();
+        refs(&document, &mut found);
+        for reference in found {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            assert!(schemas.contains_key(name), "{reference} is missing");
+        }
+    }
+}
@@ -1,33 +1,639 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{FromRequestParts, Path, State},
+    http::{StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
 
-use kiko::data::CreateSession;
+use kiko::{
+    data::{
+        CreateSession, CreatedSession, ErrorBody, JoinRequest, Joined, RoundResult, Session,
+        SessionSummary, TopicRequest, VoteRequest,
+    },
+    errors::WebSocketError,
+    id::{ParticipantId, ResumeToken, SessionId},
+};
 
-use crate::services::SessionService;
+use super::error::action_error;
+use crate::actions;
+
+/// The participant making a request, `None` for anonymous requests.
+///
+/// Participants identify themselves with the resume token they got when joining,
+/// in an `Authorization: Bearer <resume token>` header. The token has to belong
+/// to the session in the request's path.
+pub struct Sender(pub Option<ParticipantId>);
+
+impl FromRequestParts<Arc<crate::AppState>> for Sender {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<crate::AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(authorization) = parts.headers.get(header::AUTHORIZATION) else {
+            return Ok(Sender(None));
+        };
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| ResumeToken::from(token.trim().to_string()))
+            .ok_or_else(|| action_error(WebSocketError::ResumeRejected))?;
+
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let session_id = params.get("session_id").cloned().map(SessionId::from);
+
+        match state.resume_tokens.lookup(&token) {
+            Some((token_session_id, participant_id))
+                if session_id.as_ref() == Some(&token_session_id) =>
+            {
+                Ok(Sender(Some(participant_id)))
+            }
+            _ => Err(action_error(WebSocketError::ResumeRejected)),
+        }
+    }
+}
+
+/// Responds with the updated session, as the sender is allowed to see it.
+fn session_response(
+    result: Result<Session, WebSocketError>,
+    sender: Option<&ParticipantId>,
+) -> Response {
+    match result {
+        Ok(session) => (StatusCode::OK, Json(session.redacted_for(sender))).into_response(),
+        Err(error) => action_error(error),
+    }
+}
 
 /// Handler to create a new session
+#[utoipa::path(
+    post,
+    operation_id = "create_session",
+    path = "/session",
+    tag = "sessions",
+    request_body = CreateSession,
+    responses(
+        (status = 201, description = "The new session, and the token to join it as the facilitator", body = CreatedSession),
+        (status = 400, description = "The request broke the validation rules", body = ErrorBody),
+        (status = 429, description = "Too many sessions created, retry later", body = ErrorBody),
+    )
+)]
 pub async fn create(
     State(state): State<Arc<crate::AppState>>,
     Json(payload): Json<CreateSession>,
 ) -> impl IntoResponse {
     match state.sessions.create(payload).await {
-        Ok(session) => (StatusCode::CREATED, Json(session)).into_response(),
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to create session",
-        )
-            .into_response(),
+        Ok(session) => {
+            let created = CreatedSession {
+                facilitator_token: session
+                    .facilitator_token()
+                    .expect("new sessions have a facilitator token")
+                    .to_string(),
+                session: session.redacted_for(None),
+            };
+            (StatusCode::CREATED, Json(created)).into_response()
+        }
+        Err(error) => error.into_response(),
     }
 }
 
 /// Handler to get a session by ID
+///
+/// REST callers are anonymous, so they get the observer's view with hidden votes redacted.
+#[utoipa::path(
+    get,
+    operation_id = "get_session",
+    path = "/session/{session_id}",
+    tag = "sessions",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    responses(
+        (status = 200, description = "The session", body = Session),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
 pub async fn get(
     State(state): State<Arc<crate::AppState>>,
     axum::extract::Path(session_id): axum::extract::Path<String>,
 ) -> impl IntoResponse {
     match state.sessions.get(&session_id.into()).await {
-        Ok(session) => (StatusCode::OK, Json(session)).into_response(),
-        Err(_) => (StatusCode::NOT_FOUND, "Session not found").into_response(),
+        Ok(session) => (StatusCode::OK, Json(session.redacted_for(None))).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Handler to get the completed round history of a session
+///
+/// Votes from rounds that were never revealed are redacted, except for the caller's own.
+#[utoipa::path(
+    get,
+    operation_id = "get_history",
+    path = "/session/{session_id}/history",
+    tag = "sessions",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "Completed rounds, oldest first", body = Vec<RoundResult>),
+        (status = 401, description = "The resume token is invalid", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn history(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Sender(sender): Sender,
+) -> impl IntoResponse {
+    match state.sessions.get(&session_id.into()).await {
+        Ok(session) => (StatusCode::OK, Json(session.history_for(sender.as_ref()))).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Handler to list all sessions
+///
+/// Only the summaries are listed, without participants, votes or anything that grants access.
+#[utoipa::path(
+    get,
+    operation_id = "list_sessions",
+    path = "/session",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "A summary of every active session", body = Vec<SessionSummary>),
+    )
+)]
+pub async fn list(State(state): State<Arc<crate::AppState>>) -> impl IntoResponse {
+    match state.sessions.list().await {
+        Ok(sessions) => {
+            let summaries: Vec<SessionSummary> = sessions.iter().map(Session::summary).collect();
+            (StatusCode::OK, Json(summaries)).into_response()
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Handler for the facilitator to end a session
+#[utoipa::path(
+    delete,
+    operation_id = "end_session",
+    path = "/session/{session_id}",
+    tag = "sessions",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 204, description = "The session was ended"),
+        (status = 401, description = "The resume token is invalid, or the caller hasn't joined", body = ErrorBody),
+        (status = 403, description = "Only the facilitator may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn end(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Sender(sender): Sender,
+) -> impl IntoResponse {
+    match actions::end(&state, &session_id.into(), sender).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(error) => action_error(error),
+    }
+}
+
+/// Handler to join a session as a new participant
+///
+/// Responds with the participant's ID and the resume token that authenticates
+/// their later requests. REST participants stay until they leave, or the session ends.
+#[utoipa::path(
+    post,
+    operation_id = "join_session",
+    path = "/session/{session_id}/participants",
+    tag = "participants",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    request_body = JoinRequest,
+    responses(
+        (status = 201, description = "The new participant's ID and resume token", body = Joined),
+        (status = 400, description = "The request broke the validation rules", body = ErrorBody),
+        (status = 403, description = "The facilitator token is wrong", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn join(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Json(payload): Json<JoinRequest>,
+) -> impl IntoResponse {
+    let facilitator_token = payload.facilitator_token.map(Into::into);
+    match actions::join(
+        &state,
+        &session_id.into(),
+        payload.participant_name,
+        facilitator_token,
+    )
+    .await
+    {
+        Ok((participant_id, resume_token)) => {
+            let joined = Joined {
+                participant_id: participant_id.to_string(),
+                resume_token: resume_token.to_string(),
+            };
+            (StatusCode::CREATED, Json(joined)).into_response()
+        }
+        Err(error) => action_error(error),
+    }
+}
+
+/// Handler to remove a participant, either yourself or anyone as the facilitator
+#[utoipa::path(
+    delete,
+    operation_id = "leave_session",
+    path = "/session/{session_id}/participants/{participant_id}",
+    tag = "participants",
+    params(("session_id" = String, Path, description = "The session's ID"), ("participant_id" = String, Path, description = "The participant's ID")),
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "The updated session, as the caller sees it", body = Session),
+        (status = 401, description = "The resume token is invalid, or the caller hasn't joined", body = ErrorBody),
+        (status = 403, description = "Only the facilitator, or the participant themselves, may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn leave(
+    State(state): State<Arc<crate::AppState>>,
+    Path((session_id, participant_id)): Path<(String, String)>,
+    Sender(sender): Sender,
+) -> impl IntoResponse {
+    let result = actions::leave(
+        &state,
+        &session_id.into(),
+        sender.clone(),
+        participant_id.into(),
+    )
+    .await;
+    session_response(result, sender.as_ref())
+}
+
+/// Handler for the facilitator to set the topic being pointed
+#[utoipa::path(
+    put,
+    operation_id = "set_topic",
+    path = "/session/{session_id}/topic",
+    tag = "voting",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    request_body = TopicRequest,
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "The updated session, as the caller sees it", body = Session),
+        (status = 400, description = "The request broke the validation rules", body = ErrorBody),
+        (status = 403, description = "Only the facilitator may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn set_topic(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Sender(sender): Sender,
+    Json(payload): Json<TopicRequest>,
+) -> impl IntoResponse {
+    let result =
+        actions::set_topic(&state, &session_id.into(), sender.clone(), payload.topic).await;
+    session_response(result, sender.as_ref())
+}
+
+/// Handler to cast or withdraw a vote, for yourself or anyone as the facilitator
+#[utoipa::path(
+    put,
+    operation_id = "vote",
+    path = "/session/{session_id}/votes/{participant_id}",
+    tag = "voting",
+    params(("session_id" = String, Path, description = "The session's ID"), ("participant_id" = String, Path, description = "The participant's ID")),
+    request_body = VoteRequest,
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "The updated session, as the caller sees it", body = Session),
+        (status = 400, description = "The card isn't in the session's deck", body = ErrorBody),
+        (status = 401, description = "The resume token is invalid, or the caller hasn't joined", body = ErrorBody),
+        (status = 403, description = "Only the facilitator, or the participant themselves, may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn vote(
+    State(state): State<Arc<crate::AppState>>,
+    Path((session_id, participant_id)): Path<(String, String)>,
+    Sender(sender): Sender,
+    Json(payload): Json<VoteRequest>,
+) -> impl IntoResponse {
+    let result = actions::vote(
+        &state,
+        &session_id.into(),
+        sender.clone(),
+        participant_id.into(),
+        payload.points,
+    )
+    .await;
+    session_response(result, sender.as_ref())
+}
+
+/// Handler for the facilitator to clear all votes and start a new round
+#[utoipa::path(
+    delete,
+    operation_id = "clear_votes",
+    path = "/session/{session_id}/votes",
+    tag = "voting",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "The updated session, as the caller sees it", body = Session),
+        (status = 403, description = "Only the facilitator may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn clear_votes(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Sender(sender): Sender,
+) -> impl IntoResponse {
+    let result = actions::clear_points(&state, &session_id.into(), sender.clone()).await;
+    session_response(result, sender.as_ref())
+}
+
+/// Handler for the facilitator to reveal everyone's votes
+#[utoipa::path(
+    post,
+    operation_id = "reveal_votes",
+    path = "/session/{session_id}/reveal",
+    tag = "voting",
+    params(("session_id" = String, Path, description = "The session's ID")),
+    security((), ("resume_token" = [])),
+    responses(
+        (status = 200, description = "The updated session, as the caller sees it", body = Session),
+        (status = 403, description = "Only the facilitator may do that", body = ErrorBody),
+        (status = 404, description = "No such session", body = ErrorBody),
+    )
+)]
+pub async fn reveal(
+    State(state): State<Arc<crate::AppState>>,
+    Path(session_id): Path<String>,
+    Sender(sender): Sender,
+) -> impl IntoResponse {
+    let result = actions::reveal_points(&state, &session_id.into(), sender.clone()).await;
+    session_response(result, sender.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use kiko::data::{
+        Deck, ErrorBody, ErrorCode, Field, SessionEndReason, SessionMessage, ValidationError,
+    };
+    use tower::ServiceExt;
+
+    async fn send(
+        state: &Arc<crate::AppState>,
+        method: &str,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<kiko::serde_json::Value>,
+    ) -> (StatusCode, Vec<u8>) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = crate::setup_routes(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn rest_actions_are_checked_and_published() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let create = CreateSession {
+            name: "REST".to_string(),
+            duration: std::time::Duration::from_secs(3600),
+            deck: Deck::Fibonacci,
+        };
+        let (status, body) = send(
+            &state,
+            "POST",
+            "/api/v1/session",
+            None,
+            Some(kiko::serde_json::to_value(create).unwrap()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created: CreatedSession = kiko::serde_json::from_slice(&body).unwrap();
+        let session = created.session;
+        assert!(session.facilitator_token().is_none());
+        let base = format!("/api/v1/session/{}", session.id);
+        let mut subscription = state.pub_sub.subscribe(session.id.clone()).await;
+
+        // Only whoever presents the creator's token facilitates
+        let (status, body) = send(
+            &state,
+            "POST",
+            &format!("{base}/participants"),
+            None,
+            Some(kiko::serde_json::json!({
+                "participant_name": "Alex",
+                "facilitator_token": created.facilitator_token,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let alex: Joined = kiko::serde_json::from_slice(&body).unwrap();
+        subscription.recv().await.unwrap();
+
+        // Anonymous callers and forged tokens can't change anything
+        let topic = kiko::serde_json::json!({ "topic": "Checkout" });
+        let (status, _) = send(
+            &state,
+            "PUT",
+            &format!("{base}/topic"),
+            None,
+            Some(topic.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(&state, "DELETE", &base, Some("forged"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let token = Some(alex.resume_token.as_str());
+        let (status, _) = send(&state, "PUT", &format!("{base}/topic"), token, Some(topic)).await;
+        assert_eq!(status, StatusCode::OK);
+        let event = subscription.recv().await.unwrap();
+        assert!(matches!(
+            event.message.as_ref(),
+            SessionMessage::SessionUpdate(session) if session.current_topic() == "Checkout"
+        ));
+
+        let vote = format!("{base}/votes/{}", alex.participant_id);
+        let (status, _) = send(
+            &state,
+            "PUT",
+            &vote,
+            token,
+            Some(kiko::serde_json::json!({ "points": "4" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(
+            &state,
+            "PUT",
+            &vote,
+            token,
+            Some(kiko::serde_json::json!({ "points": "5" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        subscription.recv().await.unwrap();
+
+        // Starting over archives the hidden round, voters still see their own vote in it
+        let votes = format!("{base}/votes");
+        let (status, _) = send(&state, "DELETE", &votes, token, None).await;
+        assert_eq!(status, StatusCode::OK);
+        subscription.recv().await.unwrap();
+        let history = format!("{base}/history");
+        let (status, body) = send(&state, "GET", &history, token, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let rounds: Vec<RoundResult> = kiko::serde_json::from_slice(&body).unwrap();
+        assert_eq!(rounds[0].votes()[0].points(), Some("5"));
+        let (_, body) = send(&state, "GET", &history, None, None).await;
+        let rounds: Vec<RoundResult> = kiko::serde_json::from_slice(&body).unwrap();
+        assert_eq!(rounds[0].votes()[0].points(), None);
+        let (status, _) = send(&state, "GET", &history, Some("forged"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(&state, "POST", &format!("{base}/reveal"), token, None).await;
+        assert_eq!(status, StatusCode::OK);
+        subscription.recv().await.unwrap();
+
+        let (status, _) = send(&state, "DELETE", &base, token, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let event = subscription.recv().await.unwrap();
+        assert!(matches!(
+            event.message.as_ref(),
+            SessionMessage::SessionEnded(SessionEndReason::Closed)
+        ));
+        let (status, _) = send(&state, "GET", &base, None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn errors_have_a_json_body() {
+        let state = Arc::new(crate::AppState::in_memory());
+
+        let (status, body) = send(&state, "GET", "/api/v1/session/missing", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let error: ErrorBody = kiko::serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, ErrorCode::SessionNotFound);
+        assert_eq!(error.message, "Session missing not found");
+
+        let create = kiko::serde_json::to_value(CreateSession {
+            name: " ".to_string(),
+            duration: std::time::Duration::from_secs(3600),
+            deck: Deck::Fibonacci,
+        })
+        .unwrap();
+        let (status, body) = send(&state, "POST", "/api/v1/session", None, Some(create)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error: ErrorBody = kiko::serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, ErrorCode::ValidationFailed);
+        assert_eq!(
+            error.violations,
+            vec![ValidationError::Empty {
+                field: Field::SessionName
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn listing_only_shows_summaries() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let create = kiko::serde_json::to_value(CreateSession {
+            name: "Listed".to_string(),
+            duration: std::time::Duration::from_secs(3600),
+            deck: Deck::Fibonacci,
+        })
+        .unwrap();
+        let (_, body) = send(&state, "POST", "/api/v1/session", None, Some(create)).await;
+        let created: CreatedSession = kiko::serde_json::from_slice(&body).unwrap();
+        let (_, body) = send(
+            &state,
+            "POST",
+            &format!("/api/v1/session/{}/participants", created.session.id),
+            None,
+            Some(kiko::serde_json::json!({
+                "participant_name": "Alex",
+                "facilitator_token": created.facilitator_token,
+            })),
+        )
+        .await;
+        let alex: Joined = kiko::serde_json::from_slice(&body).unwrap();
+
+        let (status, body) = send(&state, "GET", "/api/v1/session", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let summaries: Vec<SessionSummary> = kiko::serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            summaries,
+            vec![SessionSummary {
+                id: created.session.id.clone(),
+                name: "Listed".to_string(),
+                participant_count: 1,
+            }]
+        );
+
+        // Nothing in the listing lets anyone act as a participant or the facilitator
+        let body = String::from_utf8(body).unwrap();
+        assert!(!body.contains(&created.facilitator_token));
+        assert!(!body.contains(&alex.resume_token));
+        assert!(!body.contains(&alex.participant_id));
+    }
+
+    #[tokio::test]
+    async fn joining_stops_at_the_configured_size() {
+        let mut state = crate::AppState::in_memory();
+        state.config.sessions.max_participants = 1;
+        let state = Arc::new(state);
+        let create = kiko::serde_json::to_value(CreateSession {
+            name: "Small".to_string(),
+            duration: std::time::Duration::from_secs(3600),
+            deck: Deck::Fibonacci,
+        })
+        .unwrap();
+        let (_, body) = send(&state, "POST", "/api/v1/session", None, Some(create)).await;
+        let created: CreatedSession = kiko::serde_json::from_slice(&body).unwrap();
+        let participants = format!("/api/v1/session/{}/participants", created.session.id);
+
+        for (name, expected) in [
+            ("Alex", StatusCode::CREATED),
+            ("Sam", StatusCode::BAD_REQUEST),
+        ] {
+            let join = kiko::serde_json::json!({ "participant_name": name });
+            let (status, body) = send(&state, "POST", &participants, None, Some(join)).await;
+            assert_eq!(status, expected);
+            if status == StatusCode::BAD_REQUEST {
+                let error: ErrorBody = kiko::serde_json::from_slice(&body).unwrap();
+                assert_eq!(
+                    error.violations,
+                    vec![ValidationError::SessionFull { max: 1 }]
+                );
+            }
+        }
     }
 }
@@ -1,19 +1,36 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
     extract::{
         ConnectInfo, State,
         ws::{self, WebSocket, WebSocketUpgrade},
     },
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
-use kiko::{data::SessionMessage, errors::WebSocketError};
-use kiko::{id::SessionId, tracing};
+use kiko::{
+    data::{
+        ClientMessage, ErrorBody, Hello, Joined, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+        ServerMessage, SessionMessage, Welcome,
+    },
+    errors::WebSocketError,
+};
+use kiko::{
+    id::{ParticipantId, ResumeToken, SessionId},
+    tracing::{self, Instrument},
+};
 use kiko::{log, serde_json};
 
-use crate::services::SessionService;
+use crate::{
+    actions::{self, modify_session},
+    limits::{self, MessageLimiter, SocketPermit},
+    messaging::{RecvError, Subscription},
+    services::Claim,
+};
+
+/// How long a new connection may take to send its `Hello` by default.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug)]
 pub enum WebSocketResponse {
@@ -24,7 +41,11 @@ pub enum WebSocketResponse {
 
 pub struct ConnectionState {
     session_id: Option<SessionId>,
-    participant_id: Option<kiko::id::ParticipantId>,
+    participant_id: Option<ParticipantId>,
+    /// This connection's claim on `participant_id`, see [`Claim`].
+    claim: Option<Claim>,
+    /// Shares `participant_id` with the listener task, which redacts updates for it.
+    viewer: watch::Sender<Option<ParticipantId>>,
     task_handle: Option<tokio::task::JoinHandle<()>>,
     outbound_rx: Option<mpsc::UnboundedReceiver<String>>,
 }
@@ -34,24 +55,35 @@ impl ConnectionState {
         Self {
             session_id: None,
             participant_id: None,
+            claim: None,
+            viewer: watch::Sender::new(None),
             task_handle: None,
             outbound_rx: None,
         }
     }
 
+    /// Records which participant this connection acts as.
+    pub fn set_participant(&mut self, participant_id: Option<ParticipantId>) {
+        if participant_id != self.participant_id {
+            self.claim = None;
+        }
+        self.viewer.send_replace(participant_id.clone());
+        self.participant_id = participant_id;
+    }
+
     pub fn is_subscribed(&self) -> bool {
         self.task_handle.is_some()
     }
 
     pub fn cleanup_subscription(&mut self) {
-        if let Some(handle) = &self.task_handle {
-            if !handle.is_finished() {
-                handle.abort();
-            }
+        if let Some(handle) = &self.task_handle
+            && !handle.is_finished()
+        {
+            handle.abort();
         }
         self.task_handle = None;
         self.outbound_rx = None;
-        self.participant_id = None;
+        self.set_participant(None);
     }
 }
 
@@ -61,6 +93,22 @@ impl Default for ConnectionState {
     }
 }
 
+/// Returns the session the connection is subscribed to.
+fn subscribed_session(conn_state: &ConnectionState) -> Result<SessionId, WebSocketError> {
+    conn_state
+        .session_id
+        .clone()
+        .ok_or(WebSocketError::NotSubscribed)
+}
+
+/// Ensures the connection hasn't joined yet, each connection is a single participant.
+fn ensure_not_joined(conn_state: &ConnectionState) -> Result<(), WebSocketError> {
+    match conn_state.participant_id {
+        Some(_) => Err(WebSocketError::AlreadyJoined),
+        None => Ok(()),
+    }
+}
+
 async fn handle_create_session(
     _create: &kiko::data::CreateSession,
     _state: &Arc<crate::AppState>,
@@ -86,14 +134,15 @@ async fn setup_subscription(
 
     conn_state.session_id = Some(session_id.clone());
 
-    // Subscribe to the session and get the notifier
-    let notifier = state.pub_sub.subscribe(session_id.clone()).await;
+    // Subscribe to the session's event log
+    let mut subscription = state.pub_sub.subscribe(session_id.clone()).await;
+    let viewer = conn_state.viewer.subscribe();
 
     // Create a channel for sending messages to the WebSocket
     let (outbound_tx, rx) = mpsc::unbounded_channel::<String>();
     conn_state.outbound_rx = Some(rx);
 
-    // Spawn a task to listen for messages and notify the WebSocket
+    // Spawn a task to forward every published event to the WebSocket
     let task_handle = tokio::spawn({
         let state = state.clone();
         let session_id = session_id.clone();
@@ -102,38 +151,53 @@ async fn setup_subscription(
                 "Starting PubSub listener task for session: {:?}",
                 session_id
             );
-            let mut notified = notifier.notified(); // Create first notification listener
             loop {
-                notified.await; // Wait for notification
-                log::debug!("Received notification for session: {:?}", session_id);
-                // Create the next notification listener BEFORE processing the current message
-                notified = notifier.notified();
-
-                if let Some(msg) = state.pub_sub.get_event(&session_id).await {
-                    match serde_json::to_string(&*msg) {
-                        Ok(json) => {
-                            log::debug!("Sending message to WebSocket: {}", json);
-                            if outbound_tx.send(json).is_err() {
-                                log::debug!(
-                                    "WebSocket channel closed, ending PubSub task for session: {:?}",
-                                    session_id
-                                );
-                                break; // Channel closed
-                            }
+                let msg = match subscription.recv().await {
+                    Ok(event) => {
+                        log::debug!("Received event {} for session: {:?}", event.id, session_id);
+                        state.metrics.event_delivered(event.published_at);
+                        event.message
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "Listener for session {:?} lagged by {} events, resyncing",
+                            session_id,
+                            skipped
+                        );
+                        match resync(&state, &session_id, &mut subscription).await {
+                            Some(snapshot) => Arc::new(snapshot),
+                            None => break, // The session is gone
                         }
-                        Err(e) => {
-                            log::error!(
-                                "Failed to serialize message for session {:?}: {}",
-                                session_id,
-                                e
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let session_ended = matches!(*msg, SessionMessage::SessionEnded(_));
+                let msg = redact_for_viewer(&msg, viewer.borrow().as_ref());
+                match serde_json::to_string(&msg) {
+                    Ok(json) => {
+                        log::debug!("Sending message to WebSocket: {}", json);
+                        if outbound_tx.send(json).is_err() {
+                            log::debug!(
+                                "WebSocket channel closed, ending PubSub task for session: {:?}",
+                                session_id
                             );
+                            break; // Channel closed
                         }
                     }
-                } else {
-                    log::debug!(
-                        "No message found after notification for session: {:?}",
-                        session_id
-                    );
+                    Err(e) => {
+                        log::error!(
+                            "Failed to serialize message for session {:?}: {}",
+                            session_id,
+                            e
+                        );
+                    }
+                }
+
+                // Nothing more will be published, dropping the sender closes the socket
+                if session_ended {
+                    log::debug!("Session {:?} ended, closing connection", session_id);
+                    break;
                 }
             }
             log::debug!("PubSub listener task ended for session: {:?}", session_id);
@@ -144,43 +208,140 @@ async fn setup_subscription(
     Ok(())
 }
 
+/// Tailors an outgoing message to the participant receiving it.
+///
+/// Session updates hide other participants' votes until they are revealed.
+fn redact_for_viewer(msg: &SessionMessage, viewer: Option<&ParticipantId>) -> SessionMessage {
+    match msg {
+        SessionMessage::SessionUpdate(session) => {
+            SessionMessage::SessionUpdate(Box::new(session.redacted_for(viewer)))
+        }
+        other => other.clone(),
+    }
+}
+
+/// Brings a lagging subscriber back in sync with a full session snapshot.
+///
+/// Returns `None` if the session no longer exists.
+async fn resync(
+    state: &Arc<crate::AppState>,
+    session_id: &SessionId,
+    subscription: &mut Subscription,
+) -> Option<SessionMessage> {
+    // Read the sequence before the snapshot, so anything newer is still delivered
+    let resync_id = state.pub_sub.last_event_id(session_id).await;
+    let session = state.sessions.get(session_id).await.ok()?;
+    subscription.skip_through(resync_id);
+
+    Some(SessionMessage::SessionUpdate(Box::new(session)))
+}
+
 async fn handle_join_session(
     join: &kiko::data::JoinSession,
     state: &Arc<crate::AppState>,
     conn_state: &mut ConnectionState,
 ) -> Result<WebSocketResponse, WebSocketError> {
-    log::info!("Joining session: {:?}", join);
-
-    let session_id: SessionId = join.session_id.clone().into();
+    log::info!("Joining session: {:?}", join.session_id);
 
-    // Check if the session exists
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(join.session_id.clone()))?;
-
-    // Add participant to the session
-    let participant_id = kiko::id::ParticipantId::new();
-    let participant =
-        kiko::data::Participant::new(participant_id.clone(), join.participant_name.clone());
-    session.add_participant(participant);
+    // Only the subscribed session can be joined, so the participant is removed on disconnect
+    let session_id = subscribed_session(conn_state)?;
+    if join.session_id != session_id.as_str() {
+        return Err(WebSocketError::NotSubscribed);
+    }
+    ensure_not_joined(conn_state)?;
+
+    let facilitator_token = join.facilitator_token.clone().map(Into::into);
+    let (participant_id, resume_token) = actions::join(
+        state,
+        &session_id,
+        join.participant_name.clone(),
+        facilitator_token,
+    )
+    .await?;
+
+    // Only the joining socket learns its ID and resume token
+    let joined = SessionMessage::Joined(Joined {
+        participant_id: participant_id.to_string(),
+        resume_token: resume_token.to_string(),
+    });
 
     // Store the participant ID in the connection state for cleanup
-    conn_state.participant_id = Some(participant_id);
+    conn_state.set_participant(Some(participant_id.clone()));
+    conn_state.claim = Some(state.resume_tokens.claim(&participant_id));
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
+    Ok(WebSocketResponse::Success(serde_json::to_string(&joined)?))
+}
 
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+async fn handle_resume_session(
+    resume: &kiko::data::ResumeSession,
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    log::info!("Resuming session: {:?}", resume.session_id);
 
-    Ok(WebSocketResponse::None)
+    let session_id: SessionId = resume.session_id.clone().into();
+    let resume_token: ResumeToken = resume.resume_token.clone().into();
+
+    // Resuming subscribes if needed, but never moves a connection to another session
+    if conn_state
+        .session_id
+        .as_ref()
+        .is_some_and(|subscribed| subscribed != &session_id)
+    {
+        return Err(WebSocketError::NotSubscribed);
+    }
+    ensure_not_joined(conn_state)?;
+
+    let participant_id = match state.resume_tokens.lookup(&resume_token) {
+        Some((token_session_id, participant_id)) if token_session_id == session_id => {
+            participant_id
+        }
+        _ => return Err(WebSocketError::ResumeRejected),
+    };
+
+    let subscribe = conn_state.session_id.is_none();
+    if subscribe {
+        setup_subscription(session_id.clone(), state, conn_state).await?;
+    }
+
+    // Claim before checking the participant is still there, a pending removal
+    // that hasn't run yet will then leave them alone
+    let claim = state.resume_tokens.claim(&participant_id);
+    conn_state.set_participant(Some(participant_id.clone()));
+    conn_state.claim = Some(claim);
+
+    // Publishes a fresh update, which now includes our own vote
+    let result = modify_session(state, &session_id, {
+        let participant_id = participant_id.clone();
+        move |session| {
+            if session
+                .participants()
+                .iter()
+                .any(|p| p.id() == &participant_id)
+            {
+                Ok(())
+            } else {
+                Err(WebSocketError::ResumeRejected)
+            }
+        }
+    })
+    .await;
+    if let Err(e) = result {
+        state.resume_tokens.expire(&participant_id, claim);
+        conn_state.set_participant(None);
+        // A rejected resume leaves the connection as it was, so the client can subscribe instead
+        if subscribe {
+            conn_state.cleanup_subscription();
+            conn_state.session_id = None;
+        }
+        return Err(e);
+    }
+
+    let joined = SessionMessage::Joined(Joined {
+        participant_id: participant_id.to_string(),
+        resume_token: resume_token.to_string(),
+    });
+    Ok(WebSocketResponse::Success(serde_json::to_string(&joined)?))
 }
 
 async fn handle_subscribe_to_session(
@@ -210,32 +371,21 @@ async fn handle_add_participant(
 async fn handle_remove_participant(
     remove: &kiko::data::RemoveParticipant,
     state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
 ) -> Result<WebSocketResponse, WebSocketError> {
     log::info!("Removing participant: {:?}", remove);
 
     let session_id: SessionId = remove.session_id.clone().into();
-    let participant_id: kiko::id::ParticipantId = remove.participant_id.clone().into();
-
-    // Get the current session
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(remove.session_id.clone()))?;
+    let participant_id: ParticipantId = remove.participant_id.clone().into();
+    let sender = conn_state.participant_id.clone();
+    let leaving = sender.as_ref() == Some(&participant_id);
 
-    // Remove participant from the session
-    session.remove_participant(&participant_id);
+    actions::leave(state, &session_id, sender, participant_id).await?;
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
-
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+    // The connection keeps observing after leaving, but no longer acts as a participant
+    if leaving {
+        conn_state.set_participant(None);
+    }
 
     Ok(WebSocketResponse::None)
 }
@@ -252,33 +402,16 @@ async fn handle_session_update(
 async fn handle_point_session(
     point: &kiko::data::PointSession,
     state: &Arc<crate::AppState>,
-    _conn_state: &mut ConnectionState,
+    conn_state: &mut ConnectionState,
 ) -> Result<WebSocketResponse, WebSocketError> {
     log::info!("Pointing session: {:?}", point);
 
     let session_id: SessionId = point.session_id.clone().into();
-    let participant_id: kiko::id::ParticipantId = point.participant_id.clone().into();
-
-    // Get the current session
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(point.session_id.clone()))?;
-
-    // Add points for the participant
-    session.point(&participant_id, point.points);
+    let participant_id: ParticipantId = point.participant_id.clone().into();
+    let sender = conn_state.participant_id.clone();
+    let points = point.points.clone();
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
-
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+    actions::vote(state, &session_id, sender, participant_id, points).await?;
 
     Ok(WebSocketResponse::None)
 }
@@ -290,110 +423,134 @@ async fn handle_set_topic(
 ) -> Result<WebSocketResponse, WebSocketError> {
     log::info!("Setting topic: {:?}", topic);
 
-    let session_id = match &conn_state.session_id {
-        Some(id) => id.clone(),
-        None => return Err(WebSocketError::NotSubscribed),
-    };
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
+    actions::set_topic(state, &session_id, sender, topic.clone()).await?;
 
-    // Get the current session
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(session_id.to_string()))?;
+    Ok(WebSocketResponse::None)
+}
 
-    // Set the new topic
-    session.set_topic(topic.clone());
+async fn handle_clear_points(
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    log::info!("Clearing points");
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
 
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+    actions::clear_points(state, &session_id, sender).await?;
 
     Ok(WebSocketResponse::None)
 }
 
-async fn handle_clear_points(
+async fn handle_toggle_hide_points(
     state: &Arc<crate::AppState>,
     conn_state: &mut ConnectionState,
 ) -> Result<WebSocketResponse, WebSocketError> {
-    log::info!("Clearing points");
+    log::info!("Toggling hide points");
 
-    let session_id = match &conn_state.session_id {
-        Some(id) => id.clone(),
-        None => return Err(WebSocketError::NotSubscribed),
-    };
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
+
+    actions::toggle_hide_points(state, &session_id, sender).await?;
+
+    Ok(WebSocketResponse::None)
+}
+
+async fn handle_add_story(
+    title: &String,
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    log::info!("Adding story: {:?}", title);
 
-    // Get the current session
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(session_id.to_string()))?;
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
 
-    // Clear all points
-    session.clear_points();
+    actions::add_story(state, &session_id, sender, title.clone()).await?;
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
+    Ok(WebSocketResponse::None)
+}
 
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+async fn handle_remove_story(
+    story_id: &str,
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    log::info!("Removing story: {:?}", story_id);
+
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
+    actions::remove_story(state, &session_id, sender, story_id.into()).await?;
 
     Ok(WebSocketResponse::None)
 }
 
-async fn handle_toggle_hide_points(
+async fn handle_reorder_story(
+    reorder: &kiko::data::ReorderStory,
     state: &Arc<crate::AppState>,
     conn_state: &mut ConnectionState,
 ) -> Result<WebSocketResponse, WebSocketError> {
-    log::info!("Toggling hide points");
+    log::info!("Reordering story: {:?}", reorder);
 
-    let session_id = match &conn_state.session_id {
-        Some(id) => id.clone(),
-        None => return Err(WebSocketError::NotSubscribed),
-    };
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
+    let story_id = reorder.story_id.clone().into();
+
+    actions::reorder_story(state, &session_id, sender, story_id, reorder.position).await?;
 
-    // Get the current session
-    let mut session = state
-        .sessions
-        .get(&session_id)
-        .await
-        .map_err(|_| WebSocketError::SessionNotFound(session_id.to_string()))?;
+    Ok(WebSocketResponse::None)
+}
 
-    session.toggle_hide_points();
+async fn handle_next_story(
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    log::info!("Advancing to next story");
 
-    // Update the session in storage
-    state
-        .sessions
-        .update(&session_id, &session)
-        .await
-        .map_err(|_| WebSocketError::InvalidMessage("Failed to update session".to_string()))?;
+    let session_id = subscribed_session(conn_state)?;
+    let sender = conn_state.participant_id.clone();
 
-    // Broadcast the updated session to all subscribers
-    let update_message = SessionMessage::SessionUpdate(session);
-    state.pub_sub.publish(session_id, update_message).await;
+    actions::next_story(state, &session_id, sender).await?;
 
     Ok(WebSocketResponse::None)
 }
 
-async fn send_error(socket: &mut WebSocket, error: &WebSocketError) -> bool {
-    let error_msg = error.to_string();
-    log::error!("{}", error_msg);
+async fn send_error(
+    socket: &mut WebSocket,
+    error: &WebSocketError,
+    request_id: Option<String>,
+) -> bool {
+    log::error!("{}", error);
+
+    let frame = ServerMessage::Error {
+        code: error.code(),
+        message: error.to_string(),
+        request_id,
+    };
+    send_frame(socket, &frame).await
+}
+
+/// Acknowledges a handled request, if the client asked for it by sending an ID.
+async fn send_ack(socket: &mut WebSocket, request_id: Option<String>) -> bool {
+    match request_id {
+        Some(request_id) => send_frame(socket, &ServerMessage::Ack { request_id }).await,
+        None => true,
+    }
+}
 
-    if let Err(e) = socket.send(ws::Message::Text(error_msg.into())).await {
-        log::error!("Failed to send error message: {}", e);
+async fn send_frame(socket: &mut WebSocket, frame: &ServerMessage) -> bool {
+    let json = match serde_json::to_string(frame) {
+        Ok(json) => json,
+        Err(e) => {
+            log::error!("Failed to serialize frame: {}", e);
+            return true;
+        }
+    };
+
+    if let Err(e) = socket.send(ws::Message::Text(json.into())).await {
+        log::error!("Failed to send frame: {}", e);
         return false; // Connection broken
     }
     true
@@ -419,30 +576,68 @@ async fn send_response(socket: &mut WebSocket, response: WebSocketResponse) -> b
 }
 
 /// Handler to upgrade HTTP connection to WebSocket
+///
+/// The WebSocket API carries live session updates, see `kiko::data::ClientMessage`
+/// and `kiko::data::ServerMessage` for the messages it speaks.
+#[utoipa::path(
+    get,
+    operation_id = "connect_websocket",
+    path = "/ws",
+    tag = "websocket",
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol"),
+        (status = 429, description = "Too many connections from this address", body = ErrorBody),
+    )
+)]
 pub async fn upgrade(
     ws: WebSocketUpgrade,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<Arc<crate::AppState>>,
 ) -> Response {
+    let ip = limits::client_ip(&addr);
+    let permit = match state
+        .limits
+        .check_upgrade(ip)
+        .and_then(|_| state.limits.open_socket(ip))
+    {
+        Ok(permit) => permit,
+        Err(limited) => {
+            log::info!("Refusing WebSocket upgrade from {}: {:?}", ip, limited);
+            return limited.into_response();
+        }
+    };
+
     // Pass the client address to the handler
-    ws.on_upgrade(move |socket| handle_socket(socket, addr, state))
+    ws.max_message_size(state.limits.config().max_message_bytes)
+        .on_upgrade(move |socket| handle_socket(socket, addr, state, permit))
 }
 
 /// Handle WebSocket connection
-#[tracing::instrument(name = "websocket", skip(socket, state))]
+#[tracing::instrument(
+    name = "websocket",
+    skip(socket, state, _permit),
+    fields(client_name = tracing::field::Empty)
+)]
 async fn handle_socket(
     mut socket: WebSocket,
     client_addr: SocketAddr,
     state: Arc<crate::AppState>,
+    _permit: SocketPermit,
 ) {
     log::debug!("Connection established");
+    let _open = state.metrics.websocket_opened();
+    if !handshake(&mut socket, state.config.sessions.handshake_timeout).await {
+        log::debug!("Handshake failed, closing connection");
+        return;
+    }
     let mut conn_state = ConnectionState::new();
+    let messages = state.limits.message_limiter();
 
     loop {
         tokio::select! {
             // Handle incoming WebSocket messages
             msg = socket.recv() => {
-                if !handle_incoming_message(msg, &mut socket, &mut conn_state, &state, client_addr).await {
+                if !handle_incoming_message(msg, &mut socket, &mut conn_state, &state, &messages, client_addr).await {
                     break;
                 }
             }
@@ -466,16 +661,83 @@ async fn handle_socket(
     log::debug!("Connection ended");
 }
 
+/// Waits for the client's `Hello` and answers it with a `Welcome`.
+///
+/// Clients that don't start with a `Hello`, or speak a protocol version the server
+/// doesn't, get an error frame instead. Returns false if the connection should be closed.
+async fn handshake(socket: &mut WebSocket, timeout: Duration) -> bool {
+    let first_message = tokio::time::timeout(timeout, async {
+        loop {
+            match socket.recv().await {
+                Some(Ok(ws::Message::Text(text))) => return Some(text),
+                Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => return None,
+                _ => continue, // Pings and the like don't count
+            }
+        }
+    })
+    .await;
+
+    let text = match first_message {
+        Ok(Some(text)) => text,
+        Ok(None) => return false,
+        Err(_) => {
+            log::debug!("No Hello within {:?}", timeout);
+            return false;
+        }
+    };
+
+    let result = match serde_json::from_str::<ClientMessage>(&text) {
+        Ok(ClientMessage::Hello(hello)) => accept_hello(&hello),
+        _ => Err(WebSocketError::HandshakeRequired),
+    };
+
+    match result {
+        Ok(welcome) => send_frame(socket, &ServerMessage::Welcome(welcome)).await,
+        Err(error) => {
+            send_error(socket, &error, None).await;
+            false
+        }
+    }
+}
+
+/// Checks that a client's protocol version is one the server understands.
+fn accept_hello(hello: &Hello) -> Result<Welcome, WebSocketError> {
+    tracing::Span::current().record("client_name", hello.client_name.as_str());
+    log::debug!(
+        "Client {} speaks protocol version {}",
+        hello.client_name,
+        hello.protocol_version
+    );
+
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&hello.protocol_version) {
+        return Err(WebSocketError::IncompatibleProtocol(hello.protocol_version));
+    }
+
+    Ok(Welcome {
+        protocol_version: PROTOCOL_VERSION,
+        min_protocol_version: MIN_PROTOCOL_VERSION,
+    })
+}
+
 async fn handle_incoming_message(
     msg: Option<Result<ws::Message, axum::Error>>,
     socket: &mut WebSocket,
     conn_state: &mut ConnectionState,
     state: &Arc<crate::AppState>,
+    messages: &MessageLimiter,
     client_addr: SocketAddr,
 ) -> bool {
     match msg {
         Some(Ok(ws::Message::Text(text))) => {
-            handle_text_message(text.to_string(), socket, conn_state, state, client_addr).await
+            handle_text_message(
+                text.to_string(),
+                socket,
+                conn_state,
+                state,
+                messages,
+                client_addr,
+            )
+            .await
         }
         Some(Ok(ws::Message::Close(_))) => {
             log::debug!("Connection closed by client");
@@ -501,29 +763,78 @@ async fn handle_text_message(
     socket: &mut WebSocket,
     conn_state: &mut ConnectionState,
     state: &Arc<crate::AppState>,
+    messages: &MessageLimiter,
     client_addr: SocketAddr,
 ) -> bool {
-    let session_msg = match serde_json::from_str::<SessionMessage>(&text) {
-        Ok(msg) => msg,
+    // Every frame counts against the budget, even ones that don't parse
+    if messages.check().is_err() {
+        log::warn!(
+            "Dropping a message from {}, over the message rate",
+            client_addr
+        );
+        let request_id = ClientMessage::peek_request_id(&text);
+        return send_error(socket, &WebSocketError::RateLimited, request_id).await;
+    }
+
+    let (request_id, session_msg) = match serde_json::from_str::<ClientMessage>(&text) {
+        Ok(ClientMessage::Request {
+            request_id,
+            message,
+        }) => (Some(request_id), message),
+        Ok(ClientMessage::Session(message)) => (None, message),
+        Ok(ClientMessage::Hello(_)) => {
+            log::warn!("Ignoring repeated Hello from {}", client_addr);
+            return true;
+        }
         Err(e) => {
+            // A request with a malformed message still gets its answer matched up
             let error = WebSocketError::InvalidMessage(e.to_string());
-            return send_error(socket, &error).await;
+            let request_id = ClientMessage::peek_request_id(&text);
+            return send_error(socket, &error, request_id).await;
         }
     };
 
+    state.metrics.message_received(session_msg.kind());
+
     log::debug!("Received message from {}: {:?}", client_addr, session_msg);
 
-    let result = match &session_msg {
+    let span = tracing::info_span!(
+        "request",
+        request_id = request_id.as_deref(),
+        kind = session_msg.kind()
+    );
+    let result = dispatch_message(&session_msg, state, conn_state)
+        .instrument(span)
+        .await;
+
+    match result {
+        Ok(response) => send_response(socket, response).await && send_ack(socket, request_id).await,
+        Err(error) => send_error(socket, &error, request_id).await,
+    }
+}
+
+/// Routes a client message to its handler.
+async fn dispatch_message(
+    session_msg: &SessionMessage,
+    state: &Arc<crate::AppState>,
+    conn_state: &mut ConnectionState,
+) -> Result<WebSocketResponse, WebSocketError> {
+    match session_msg {
         // Session management
         SessionMessage::CreateSession(create) => handle_create_session(create, state).await,
         SessionMessage::JoinSession(join) => handle_join_session(join, state, conn_state).await,
+        SessionMessage::ResumeSession(resume) => {
+            handle_resume_session(resume, state, conn_state).await
+        }
         SessionMessage::SubscribeToSession(subscribe) => {
             handle_subscribe_to_session(subscribe, state, conn_state).await
         }
 
         // Participant management
         SessionMessage::AddParticipant(add) => handle_add_participant(add, state).await,
-        SessionMessage::RemoveParticipant(remove) => handle_remove_participant(remove, state).await,
+        SessionMessage::RemoveParticipant(remove) => {
+            handle_remove_participant(remove, state, conn_state).await
+        }
 
         // Session actions
         SessionMessage::SetTopic(topic) => handle_set_topic(topic, state, conn_state).await,
@@ -531,13 +842,26 @@ async fn handle_text_message(
         SessionMessage::ClearPoints => handle_clear_points(state, conn_state).await,
         SessionMessage::ToggleHidePoints => handle_toggle_hide_points(state, conn_state).await,
 
+        // Story backlog
+        SessionMessage::AddStory(title) => handle_add_story(title, state, conn_state).await,
+        SessionMessage::RemoveStory(story_id) => {
+            handle_remove_story(story_id, state, conn_state).await
+        }
+        SessionMessage::ReorderStory(reorder) => {
+            handle_reorder_story(reorder, state, conn_state).await
+        }
+        SessionMessage::NextStory => handle_next_story(state, conn_state).await,
+
         // Session updates (usually from server-side, but handled here for completeness)
         SessionMessage::SessionUpdate(update) => handle_session_update(update, state).await,
-    };
-
-    match result {
-        Ok(response) => send_response(socket, response).await,
-        Err(error) => send_error(socket, &error).await,
+        SessionMessage::Joined(_) => {
+            log::warn!("Ignoring Joined message from client");
+            Ok(WebSocketResponse::None)
+        }
+        SessionMessage::SessionEnded(_) => {
+            log::warn!("Ignoring SessionEnded message from client");
+            Ok(WebSocketResponse::None)
+        }
     }
 }
 
@@ -559,34 +883,532 @@ async fn handle_outbound_message(outbound_msg: Option<String>, socket: &mut WebS
 }
 
 async fn cleanup_connection(conn_state: &mut ConnectionState, state: &Arc<crate::AppState>) {
-    // Remove participant from session if they were joined
+    // Give a joined participant the grace period to reconnect before removing them
     if let (Some(session_id), Some(participant_id)) =
         (&conn_state.session_id, &conn_state.participant_id)
     {
-        log::info!(
-            "Cleaning up participant {:?} from session {:?}",
-            participant_id,
-            session_id
-        );
-
-        // Remove participant from session
-        if let Ok(mut session) = state.sessions.get(session_id).await {
-            session.remove_participant(participant_id);
-
-            if (state.sessions.update(session_id, &session).await).is_ok() {
-                // Broadcast the updated session to all remaining subscribers
-                let update_message = SessionMessage::SessionUpdate(session);
-                state
-                    .pub_sub
-                    .publish(session_id.clone(), update_message)
-                    .await;
+        match conn_state.claim {
+            Some(claim) => {
+                log::info!(
+                    "Participant {:?} disconnected from session {:?}, removing in {:?} unless resumed",
+                    participant_id,
+                    session_id,
+                    state.resume_tokens.grace_period()
+                );
+                tokio::spawn(expire_participant(
+                    state.clone(),
+                    session_id.clone(),
+                    participant_id.clone(),
+                    claim,
+                ));
             }
+            None => remove_participant(state, session_id, participant_id.clone()).await,
         }
     }
 
     conn_state.cleanup_subscription();
 
-    // Note: We don't call pub_sub.cleanup_session here because other subscribers
-    // might still be connected to this session. The PubSub system will handle
-    // cleanup when there are no more subscribers.
+    // The session's channel outlives its last subscriber, keeping its event IDs going
+    // for whoever subscribes next. `end_session` drops it when the session ends.
+}
+
+/// Removes a disconnected participant once the grace period has passed,
+/// unless another connection has resumed them in the meantime.
+async fn expire_participant(
+    state: Arc<crate::AppState>,
+    session_id: SessionId,
+    participant_id: ParticipantId,
+    claim: Claim,
+) {
+    tokio::time::sleep(state.resume_tokens.grace_period()).await;
+    if !state.resume_tokens.is_current(&participant_id, claim) {
+        log::debug!("Participant {:?} was resumed, keeping them", participant_id);
+        return;
+    }
+
+    // Check the claim again under the session lock, a resume may have just happened
+    log::info!(
+        "Removing participant {:?} from session {:?} after the grace period",
+        participant_id,
+        session_id
+    );
+    let tokens_state = state.clone();
+    if let Err(e) = modify_session(&state, &session_id, move |session| {
+        if tokens_state.resume_tokens.expire(&participant_id, claim) {
+            session.remove_participant(&participant_id);
+        }
+        Ok(())
+    })
+    .await
+    {
+        log::debug!("Could not remove expired participant: {}", e);
+    }
+}
+
+/// Removes a participant right away, the session may already have ended.
+async fn remove_participant(
+    state: &Arc<crate::AppState>,
+    session_id: &SessionId,
+    participant_id: ParticipantId,
+) {
+    log::info!(
+        "Cleaning up participant {:?} from session {:?}",
+        participant_id,
+        session_id
+    );
+    state.resume_tokens.revoke_participant(&participant_id);
+    if let Err(e) = modify_session(state, session_id, move |session| {
+        session.remove_participant(&participant_id);
+        Ok(())
+    })
+    .await
+    {
+        log::debug!("Could not remove participant during cleanup: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kiko::data::{CreateSession, Deck, MAX_PARTICIPANTS, PointSession};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_votes_are_not_lost() {
+        // As many voters as a session can hold
+        const VOTERS: usize = MAX_PARTICIPANTS;
+
+        let state = Arc::new(crate::AppState::in_memory());
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Concurrency".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+
+        let mut voters = Vec::new();
+        for i in 0..VOTERS {
+            let participant = state
+                .sessions
+                .join(&session.id, &format!("Voter {i}"))
+                .await
+                .unwrap();
+            voters.push(participant.id().clone());
+        }
+        let mut subscription = state.pub_sub.subscribe(session.id.clone()).await;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for participant_id in voters {
+            let state = state.clone();
+            let point = PointSession {
+                session_id: session.id.to_string(),
+                participant_id: participant_id.to_string(),
+                points: Some("8".to_string()),
+            };
+            tasks.spawn(async move {
+                let mut conn_state = ConnectionState::new();
+                conn_state.set_participant(Some(participant_id));
+                handle_point_session(&point, &state, &mut conn_state)
+                    .await
+                    .unwrap();
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            result.unwrap();
+        }
+
+        let session = state.sessions.get(&session.id).await.unwrap();
+        assert_eq!(session.current_points().len(), VOTERS);
+
+        // However the broadcasts interleaved, the last one has every vote
+        let last_event_id = state.pub_sub.last_event_id(&session.id).await;
+        let mut last_update = None;
+        while subscription.last_seen() < last_event_id {
+            let event = subscription.recv().await.unwrap();
+            if let SessionMessage::SessionUpdate(update) = event.message.as_ref() {
+                last_update = Some(update.clone());
+            }
+        }
+        let last_update = last_update.expect("Expected the votes to be broadcast");
+        assert_eq!(last_update.current_points().len(), VOTERS);
+        assert_eq!(last_update.version(), session.version());
+    }
+
+    #[test]
+    fn updates_are_redacted_per_viewer() {
+        let mut session = kiko::data::Session::new(
+            "Redacted".to_string(),
+            std::time::Duration::from_secs(3600),
+            Deck::Fibonacci,
+        );
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+        session.add_participant(kiko::data::Participant::new(alex.clone(), "Alex".into()));
+        session.add_participant(kiko::data::Participant::new(sam.clone(), "Sam".into()));
+        session.point(&alex, Some("5".to_string()));
+        session.point(&sam, Some("13".to_string()));
+
+        let msg = SessionMessage::SessionUpdate(Box::new(session));
+        let SessionMessage::SessionUpdate(view) = redact_for_viewer(&msg, Some(&sam)) else {
+            panic!("Expected a session update");
+        };
+        assert_eq!(
+            view.current_points().get(&sam),
+            Some(&Some("13".to_string()))
+        );
+        assert!(view.current_points().get(&alex).is_none());
+        assert!(view.has_voted(&alex));
+    }
+
+    #[tokio::test]
+    async fn participants_can_only_vote_and_leave_as_themselves() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Identity".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+        let facilitator = state.sessions.join(&session.id, "Alex").await.unwrap();
+        let sam = state.sessions.join(&session.id, "Sam").await.unwrap();
+        let kim = state.sessions.join(&session.id, "Kim").await.unwrap();
+        let (facilitator_id, token) = (
+            facilitator.id().clone(),
+            session.facilitator_token().unwrap().clone(),
+        );
+        state
+            .sessions
+            .modify(
+                &session.id,
+                Box::new(move |session| {
+                    session.claim_facilitator(&facilitator_id, &token);
+                    Ok(())
+                }),
+            )
+            .await
+            .unwrap();
+
+        let point_as = |target: &kiko::data::Participant| PointSession {
+            session_id: session.id.to_string(),
+            participant_id: target.id().to_string(),
+            points: Some("3".to_string()),
+        };
+        let remove = |target: &kiko::data::Participant| kiko::data::RemoveParticipant {
+            session_id: session.id.to_string(),
+            participant_id: target.id().to_string(),
+        };
+
+        // Observers can't vote at all
+        let mut observer = ConnectionState::new();
+        let result = handle_point_session(&point_as(&sam), &state, &mut observer).await;
+        assert!(matches!(result, Err(WebSocketError::NotJoined)));
+
+        // Participants can't vote for or kick each other
+        let mut sam_conn = ConnectionState::new();
+        sam_conn.set_participant(Some(sam.id().clone()));
+        let result = handle_point_session(&point_as(&kim), &state, &mut sam_conn).await;
+        assert!(matches!(result, Err(WebSocketError::NotYourself)));
+        let result = handle_remove_participant(&remove(&kim), &state, &mut sam_conn).await;
+        assert!(matches!(result, Err(WebSocketError::NotYourself)));
+
+        // But can vote and leave as themselves
+        handle_point_session(&point_as(&sam), &state, &mut sam_conn)
+            .await
+            .unwrap();
+        handle_remove_participant(&remove(&sam), &state, &mut sam_conn)
+            .await
+            .unwrap();
+        assert!(sam_conn.participant_id.is_none());
+
+        // The facilitator may remove anyone
+        let mut facilitator_conn = ConnectionState::new();
+        facilitator_conn.set_participant(Some(facilitator.id().clone()));
+        handle_remove_participant(&remove(&kim), &state, &mut facilitator_conn)
+            .await
+            .unwrap();
+
+        let session = state.sessions.get(&session.id).await.unwrap();
+        assert_eq!(session.participants().len(), 1);
+        assert!(session.current_points().get(kim.id()).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejected_changes_are_not_stored() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Guarded".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+
+        // A connection that never joined can't act as the facilitator
+        let mut conn_state = ConnectionState::new();
+        conn_state.session_id = Some(session.id.clone());
+        let result = handle_set_topic(&"Sneaky".to_string(), &state, &mut conn_state).await;
+        assert!(matches!(result, Err(WebSocketError::NotFacilitator)));
+
+        let session = state.sessions.get(&session.id).await.unwrap();
+        assert_eq!(session.current_topic(), "");
+    }
+
+    #[tokio::test]
+    async fn joining_replies_with_participant_id_and_resume_token() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Joined".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+
+        let join = kiko::data::JoinSession {
+            session_id: session.id.to_string(),
+            participant_name: "Alex".to_string(),
+            facilitator_token: session.facilitator_token().map(ToString::to_string),
+        };
+        let subscribe = kiko::data::SubscribeToSession {
+            session_id: session.id.to_string(),
+        };
+        let mut conn_state = ConnectionState::new();
+        handle_subscribe_to_session(&subscribe, &state, &mut conn_state)
+            .await
+            .unwrap();
+        let response = handle_join_session(&join, &state, &mut conn_state)
+            .await
+            .unwrap();
+
+        let WebSocketResponse::Success(json) = response else {
+            panic!("Expected a reply to the joining socket");
+        };
+        let Ok(SessionMessage::Joined(joined)) = serde_json::from_str(&json) else {
+            panic!("Expected a Joined message, got {json}");
+        };
+
+        let participant_id: ParticipantId = joined.participant_id.into();
+        assert_eq!(conn_state.participant_id.as_ref(), Some(&participant_id));
+        let session = state.sessions.get(&session.id).await.unwrap();
+        assert_eq!(session.participants()[0].id(), &participant_id);
+        assert!(session.is_facilitator(&participant_id));
+        assert_eq!(
+            state.resume_tokens.lookup(&joined.resume_token.into()),
+            Some((session.id.clone(), participant_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn joining_with_a_wrong_facilitator_token_is_refused() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Joined".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+
+        let join = kiko::data::JoinSession {
+            session_id: session.id.to_string(),
+            participant_name: "Mallory".to_string(),
+            facilitator_token: Some("guessed".to_string()),
+        };
+        let subscribe = kiko::data::SubscribeToSession {
+            session_id: session.id.to_string(),
+        };
+        let mut conn_state = ConnectionState::new();
+        handle_subscribe_to_session(&subscribe, &state, &mut conn_state)
+            .await
+            .unwrap();
+        let result = handle_join_session(&join, &state, &mut conn_state).await;
+
+        assert!(matches!(result, Err(WebSocketError::NotFacilitator)));
+        assert!(conn_state.participant_id.is_none());
+        let session = state.sessions.get(&session.id).await.unwrap();
+        assert!(session.participants().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_the_subscribed_session_can_be_joined_once() {
+        let state = Arc::new(crate::AppState::in_memory());
+        let join = |session_id: &SessionId| kiko::data::JoinSession {
+            session_id: session_id.to_string(),
+            participant_name: "Sam".to_string(),
+            facilitator_token: None,
+        };
+
+        // Joining needs a subscription, or the participant would never be cleaned up
+        let (session_id, _) = join_new_session(&state, &mut ConnectionState::new()).await;
+        let result =
+            handle_join_session(&join(&session_id), &state, &mut ConnectionState::new()).await;
+        assert!(matches!(result, Err(WebSocketError::NotSubscribed)));
+
+        let mut conn_state = ConnectionState::new();
+        join_new_session(&state, &mut conn_state).await;
+
+        // Other sessions can't be joined through this connection
+        let result = handle_join_session(&join(&session_id), &state, &mut conn_state).await;
+        assert!(matches!(result, Err(WebSocketError::NotSubscribed)));
+
+        // Nor can the same connection join twice
+        let subscribed = conn_state.session_id.clone().unwrap();
+        let result = handle_join_session(&join(&subscribed), &state, &mut conn_state).await;
+        assert!(matches!(result, Err(WebSocketError::AlreadyJoined)));
+        let session = state.sessions.get(&subscribed).await.unwrap();
+        assert_eq!(session.participants().len(), 1);
+    }
+
+    /// Joins a fresh session on a new connection, returning the `Joined` reply.
+    async fn join_new_session(
+        state: &Arc<crate::AppState>,
+        conn_state: &mut ConnectionState,
+    ) -> (SessionId, Joined) {
+        let session = state
+            .sessions
+            .create(CreateSession {
+                name: "Flaky Wi-Fi".to_string(),
+                duration: std::time::Duration::from_secs(3600),
+                deck: Deck::Fibonacci,
+            })
+            .await
+            .unwrap();
+        let subscribe = kiko::data::SubscribeToSession {
+            session_id: session.id.to_string(),
+        };
+        handle_subscribe_to_session(&subscribe, state, conn_state)
+            .await
+            .unwrap();
+
+        let join = kiko::data::JoinSession {
+            session_id: session.id.to_string(),
+            participant_name: "Alex".to_string(),
+            facilitator_token: None,
+        };
+        let Ok(WebSocketResponse::Success(json)) =
+            handle_join_session(&join, state, conn_state).await
+        else {
+            panic!("Expected a reply to the joining socket");
+        };
+        let Ok(SessionMessage::Joined(joined)) = serde_json::from_str(&json) else {
+            panic!("Expected a Joined message, got {json}");
+        };
+        (session.id, joined)
+    }
+
+    fn app_state_with_grace_period(grace_period: std::time::Duration) -> Arc<crate::AppState> {
+        let mut state = crate::AppState::in_memory();
+        state.resume_tokens = crate::services::ResumeTokens::new(grace_period);
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn dropped_participants_can_resume_with_their_vote() {
+        let state = app_state_with_grace_period(std::time::Duration::from_millis(50));
+        let mut dropped = ConnectionState::new();
+        let (session_id, joined) = join_new_session(&state, &mut dropped).await;
+
+        let point = PointSession {
+            session_id: session_id.to_string(),
+            participant_id: joined.participant_id.clone(),
+            points: Some("5".to_string()),
+        };
+        handle_point_session(&point, &state, &mut dropped)
+            .await
+            .unwrap();
+        cleanup_connection(&mut dropped, &state).await;
+
+        let resume = kiko::data::ResumeSession {
+            session_id: session_id.to_string(),
+            resume_token: joined.resume_token.clone(),
+        };
+        let mut resumed = ConnectionState::new();
+        let Ok(WebSocketResponse::Success(json)) =
+            handle_resume_session(&resume, &state, &mut resumed).await
+        else {
+            panic!("Expected a reply to the resuming socket");
+        };
+        let Ok(SessionMessage::Joined(rejoined)) = serde_json::from_str(&json) else {
+            panic!("Expected a Joined message, got {json}");
+        };
+        assert_eq!(rejoined, joined);
+        assert!(resumed.is_subscribed());
+
+        // Resuming again on the same connection, or into another session, is refused
+        let result = handle_resume_session(&resume, &state, &mut resumed).await;
+        assert!(matches!(result, Err(WebSocketError::AlreadyJoined)));
+        let other = kiko::data::ResumeSession {
+            session_id: SessionId::new().to_string(),
+            resume_token: joined.resume_token.clone(),
+        };
+        let result = handle_resume_session(&other, &state, &mut resumed).await;
+        assert!(matches!(result, Err(WebSocketError::NotSubscribed)));
+
+        // The removal scheduled by the dropped socket leaves the resumed participant alone
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let participant_id: ParticipantId = joined.participant_id.into();
+        assert_eq!(resumed.participant_id.as_ref(), Some(&participant_id));
+        let session = state.sessions.get(&session_id).await.unwrap();
+        assert_eq!(session.participants().len(), 1);
+        assert_eq!(
+            session.current_points().get(&participant_id),
+            Some(&Some("5".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn participants_are_removed_after_the_grace_period() {
+        let state = app_state_with_grace_period(std::time::Duration::from_millis(50));
+        let mut dropped = ConnectionState::new();
+        let (session_id, joined) = join_new_session(&state, &mut dropped).await;
+        cleanup_connection(&mut dropped, &state).await;
+
+        // Still there during the grace period
+        let session = state.sessions.get(&session_id).await.unwrap();
+        assert_eq!(session.participants().len(), 1);
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let session = state.sessions.get(&session_id).await.unwrap();
+        assert!(session.participants().is_empty());
+
+        let resume = kiko::data::ResumeSession {
+            session_id: session_id.to_string(),
+            resume_token: joined.resume_token,
+        };
+        let mut late = ConnectionState::new();
+        let result = handle_resume_session(&resume, &state, &mut late).await;
+        assert!(matches!(result, Err(WebSocketError::ResumeRejected)));
+        assert!(late.participant_id.is_none());
+        assert!(!late.is_subscribed());
+    }
+
+    #[test]
+    fn hello_must_speak_a_supported_protocol_version() {
+        let hello = |protocol_version| Hello {
+            protocol_version,
+            client_name: "test".to_string(),
+        };
+
+        let welcome = accept_hello(&hello(PROTOCOL_VERSION)).unwrap();
+        assert_eq!(welcome.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(welcome.min_protocol_version, MIN_PROTOCOL_VERSION);
+
+        let too_new = accept_hello(&hello(PROTOCOL_VERSION + 1));
+        assert!(matches!(
+            too_new,
+            Err(WebSocketError::IncompatibleProtocol(v)) if v == PROTOCOL_VERSION + 1
+        ));
+        assert!(accept_hello(&hello(MIN_PROTOCOL_VERSION - 1)).is_err());
+    }
 }
@@ -0,0 +1,318 @@
+//! Rate limiting and abuse protection.
+//!
+//! Clients are told apart by their IP address. Creating sessions and opening
+//! WebSockets are limited per IP, and each IP may only keep a handful of sockets
+//! open at once. Once connected, every socket gets its own budget of messages.
+//!
+//! HTTP clients that go over a limit get a `429 Too Many Requests` with a
+//! `Retry-After` header, WebSocket clients get a
+//! [`RateLimited`](kiko::data::ErrorCode::RateLimited) error frame.
+//!
+//! The limiters are built on `governor` directly rather than through a tower layer.
+//! Only the session creation limit is a plain per-request check, the others belong
+//! to a socket: upgrades are counted together with the socket permit in the upgrade
+//! handler, and each socket gets its own message budget for as long as it lives.
+//! Keeping them all here also lets every 429 carry the API's JSON error body.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::{DashMap, mapref::entry::Entry};
+use governor::{
+    DefaultDirectRateLimiter, DefaultKeyedRateLimiter, NotUntil, Quota,
+    clock::{Clock, DefaultClock, QuantaInstant},
+};
+
+use kiko::{data::ErrorCode, log, serde::Deserialize};
+
+use crate::handlers::v1::error::error_response;
+
+/// How often limiter state for clients that have gone quiet is dropped.
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Tunable limits, see [`crate::config`] for how to override them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "kiko::serde", default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Sessions one IP may create per minute.
+    pub session_creates_per_minute: NonZeroU32,
+    /// WebSocket connections one IP may open per minute.
+    pub websocket_upgrades_per_minute: NonZeroU32,
+    /// Sustained messages per second on a single WebSocket.
+    pub messages_per_second: NonZeroU32,
+    /// Messages a WebSocket may send in a quick burst.
+    pub message_burst: NonZeroU32,
+    /// WebSockets one IP may keep open at the same time.
+    pub max_sockets_per_ip: usize,
+    /// Largest WebSocket message accepted, in bytes.
+    pub max_message_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            session_creates_per_minute: NonZeroU32::new(10).unwrap(),
+            // Reconnects back off, but a flaky network can still cause a few in a row
+            websocket_upgrades_per_minute: NonZeroU32::new(30).unwrap(),
+            messages_per_second: NonZeroU32::new(10).unwrap(),
+            message_burst: NonZeroU32::new(20).unwrap(),
+            max_sockets_per_ip: 20,
+            max_message_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// A client went over one of the limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// How long until trying again can succeed, if that is known.
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimited {
+    fn until(not_until: NotUntil<QuantaInstant>) -> Self {
+        Self {
+            retry_after: Some(not_until.wait_time_from(DefaultClock::default().now())),
+        }
+    }
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        match self.retry_after {
+            Some(retry_after) => {
+                // Round up, so clients don't retry a moment too early
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                let mut response = error_response(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    ErrorCode::RateLimited,
+                    format!("Too many requests, retry in {seconds}s"),
+                );
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, seconds.into());
+                response
+            }
+            None => error_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::RateLimited,
+                "Too many open connections",
+            ),
+        }
+    }
+}
+
+/// Per-connection message budget, see [`Limits::message_limiter`].
+pub type MessageLimiter = DefaultDirectRateLimiter;
+
+/// Shared limiter state for all clients.
+pub struct Limits {
+    config: LimitsConfig,
+    session_creates: DefaultKeyedRateLimiter<IpAddr>,
+    upgrades: DefaultKeyedRateLimiter<IpAddr>,
+    sockets: Arc<DashMap<IpAddr, usize>>,
+}
+
+impl Limits {
+    pub fn new(config: LimitsConfig) -> Self {
+        Self {
+            session_creates: DefaultKeyedRateLimiter::keyed(Quota::per_minute(
+                config.session_creates_per_minute,
+            )),
+            upgrades: DefaultKeyedRateLimiter::keyed(Quota::per_minute(
+                config.websocket_upgrades_per_minute,
+            )),
+            sockets: Arc::new(DashMap::new()),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &LimitsConfig {
+        &self.config
+    }
+
+    /// Counts a session creation against the client's budget.
+    pub fn check_session_create(&self, ip: IpAddr) -> Result<(), RateLimited> {
+        self.session_creates
+            .check_key(&ip)
+            .map_err(RateLimited::until)
+    }
+
+    /// Counts a WebSocket upgrade against the client's budget.
+    pub fn check_upgrade(&self, ip: IpAddr) -> Result<(), RateLimited> {
+        self.upgrades.check_key(&ip).map_err(RateLimited::until)
+    }
+
+    /// Reserves one of the client's sockets until the returned permit is dropped.
+    pub fn open_socket(&self, ip: IpAddr) -> Result<SocketPermit, RateLimited> {
+        // Only granted permits are counted, so refusals never leave an empty entry behind
+        let max = self.config.max_sockets_per_ip;
+        match self.sockets.entry(ip) {
+            Entry::Occupied(mut open) if *open.get() < max => *open.get_mut() += 1,
+            Entry::Vacant(vacant) if max > 0 => {
+                vacant.insert(1);
+            }
+            _ => return Err(RateLimited { retry_after: None }),
+        }
+
+        Ok(SocketPermit {
+            ip,
+            sockets: self.sockets.clone(),
+        })
+    }
+
+    /// Creates the message budget for a new connection.
+    pub fn message_limiter(&self) -> MessageLimiter {
+        let quota = Quota::per_second(self.config.messages_per_second)
+            .allow_burst(self.config.message_burst);
+        MessageLimiter::direct(quota)
+    }
+
+    /// Drops limiter state for clients whose budget has fully recovered.
+    pub fn retain_recent(&self) {
+        self.session_creates.retain_recent();
+        self.session_creates.shrink_to_fit();
+        self.upgrades.retain_recent();
+        self.upgrades.shrink_to_fit();
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(LimitsConfig::default())
+    }
+}
+
+/// One of a client's open sockets, released when dropped.
+#[derive(Debug)]
+pub struct SocketPermit {
+    ip: IpAddr,
+    sockets: Arc<DashMap<IpAddr, usize>>,
+}
+
+impl Drop for SocketPermit {
+    fn drop(&mut self) {
+        if let Some(mut open) = self.sockets.get_mut(&self.ip) {
+            *open -= 1;
+        }
+        self.sockets.remove_if(&self.ip, |_, open| *open == 0);
+    }
+}
+
+/// Identifies the client of a request by its IP address.
+///
+/// IPv4 clients of the IPv6 listener show up as mapped addresses, so those are
+/// turned back into plain IPv4 addresses.
+pub fn client_ip(addr: &SocketAddr) -> IpAddr {
+    addr.ip().to_canonical()
+}
+
+/// Middleware limiting how many sessions each client can create.
+///
+/// Requests without connection info, e.g. in tests, aren't limited.
+pub async fn limit_session_creates(
+    State(state): State<Arc<crate::AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>()
+        && let Err(limited) = state.limits.check_session_create(client_ip(addr))
+    {
+        log::info!("Session creation rate limit exceeded for {}", addr.ip());
+        return limited.into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Spawns a background task that periodically forgets quiet clients.
+pub fn spawn_cleanup(
+    state: Arc<crate::AppState>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            state.limits.retain_recent();
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_tracked_per_ip() {
+        let limits = Limits::new(LimitsConfig {
+            session_creates_per_minute: NonZeroU32::new(2).unwrap(),
+            ..LimitsConfig::default()
+        });
+        let alex: IpAddr = "10.0.0.1".parse().unwrap();
+        let sam: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(limits.check_session_create(alex).is_ok());
+        assert!(limits.check_session_create(alex).is_ok());
+        let limited = limits.check_session_create(alex).unwrap_err();
+        assert!(
+            limited
+                .retry_after
+                .is_some_and(|wait| wait > Duration::ZERO)
+        );
+
+        assert!(limits.check_session_create(sam).is_ok());
+    }
+
+    #[test]
+    fn socket_permits_are_released_on_drop() {
+        let limits = Limits::new(LimitsConfig {
+            max_sockets_per_ip: 2,
+            ..LimitsConfig::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let first = limits.open_socket(ip).unwrap();
+        let _second = limits.open_socket(ip).unwrap();
+        assert_eq!(
+            limits.open_socket(ip).unwrap_err(),
+            RateLimited { retry_after: None }
+        );
+
+        drop(first);
+        let third = limits.open_socket(ip).unwrap();
+
+        drop((third, _second));
+        assert!(limits.sockets.is_empty());
+    }
+
+    #[test]
+    fn mapped_ipv4_clients_share_a_budget() {
+        let plain: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        let mapped: SocketAddr = "[::ffff:10.0.0.1]:5678".parse().unwrap();
+        assert_eq!(client_ip(&plain), client_ip(&mapped));
+    }
+
+    #[test]
+    fn rate_limited_responses_say_when_to_retry() {
+        let response = RateLimited {
+            retry_after: Some(Duration::from_millis(1500)),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+}
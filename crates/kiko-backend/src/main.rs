@@ -3,55 +3,105 @@
 //! A real-time session management backend built with Axum and WebSockets.
 //! Provides REST APIs for session management and WebSocket connections for live updates.
 
+pub mod actions;
+pub mod config;
 pub mod handlers;
+pub mod limits;
 pub mod messaging;
+pub mod metrics;
+pub mod reaper;
 pub mod services;
 
 use std::{net::SocketAddr, sync::Arc};
 
 use axum::{
-    Router,
+    Json, Router,
     http::{Method, header},
-    routing::{get, post},
+    middleware,
+    routing::get,
 };
 use chrono::DateTime;
 use tokio::{net::TcpListener, signal};
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use utoipa::OpenApi;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
 
 use kiko::errors::Report;
 use kiko::log;
 
-use crate::{messaging::PubSub, services::SessionServiceInMemory};
+use crate::{
+    config::{Config, StorageBackend, StorageConfig},
+    limits::Limits,
+    messaging::PubSub,
+    metrics::Metrics,
+    services::{DynSessionService, ResumeTokens, SessionServiceInMemory, SessionServiceSqlite},
+};
 
 /// Shared application state containing services and configuration.
 pub struct AppState {
     started_at: DateTime<chrono::Utc>,
-    sessions: SessionServiceInMemory,
+    config: Config,
+    sessions: Box<DynSessionService>,
     pub_sub: PubSub,
+    resume_tokens: ResumeTokens,
+    limits: Limits,
+    metrics: Metrics,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// Creates state backed by in-memory storage for tests.
+    fn in_memory() -> Self {
+        Self {
+            started_at: chrono::Utc::now(),
+            config: Config::default(),
+            sessions: Box::new(SessionServiceInMemory::new()),
+            pub_sub: PubSub::new(),
+            resume_tokens: ResumeTokens::default(),
+            limits: Limits::default(),
+            metrics: Metrics::new(),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Report> {
+    // Load and validate the configuration before anything else
+    let config = Config::load()?;
+
     // Setup logging
-    kiko::log::setup()?;
+    kiko::log::setup_with_filter(&config.log_filter)?;
 
     // Add application state
     let app_state = Arc::new(AppState {
         started_at: chrono::Utc::now(),
-        sessions: SessionServiceInMemory::new(),
+        sessions: session_service(&config.storage)?,
         pub_sub: PubSub::new(),
+        resume_tokens: ResumeTokens::new(config.sessions.resume_grace_period),
+        limits: Limits::new(config.limits.clone()),
+        metrics: Metrics::new(),
+        config,
     });
 
+    // Periodically end expired and abandoned sessions
+    reaper::spawn(
+        app_state.clone(),
+        app_state.config.sessions.sweep_interval,
+        app_state.config.sessions.empty_session_timeout,
+    );
+
+    // Forget rate limits of clients that have gone quiet
+    limits::spawn_cleanup(app_state.clone(), limits::CLEANUP_INTERVAL);
+
     // Setup the routes
+    let bind = app_state.config.bind;
     let app = setup_routes(app_state);
 
     // Setup the server
-    let port = std::env::var("KIKO_BACKEND_PORT")
-        .unwrap_or_else(|_| "3030".to_string())
-        .parse::<u16>()
-        .unwrap_or(3030);
-    let ipv6 = SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 0], port));
-    let listener = TcpListener::bind(ipv6).await?;
+    let listener = TcpListener::bind(bind).await?;
     log::info!("Starting server on http://{}", listener.local_addr()?);
     log::info!("Press Ctrl+C to stop the server");
 
@@ -68,6 +118,26 @@ async fn main() -> Result<(), Report> {
     Ok(())
 }
 
+/// Create the session service for the configured storage backend.
+///
+/// `memory` (the default) keeps sessions in memory, `sqlite` persists them to the
+/// configured database file.
+fn session_service(storage: &StorageConfig) -> Result<Box<DynSessionService>, Report> {
+    match storage.backend {
+        StorageBackend::Memory => {
+            log::info!("Storing sessions in memory");
+            Ok(Box::new(SessionServiceInMemory::new()))
+        }
+        StorageBackend::Sqlite => {
+            log::info!(
+                "Storing sessions in SQLite database {}",
+                storage.sqlite_path.display()
+            );
+            Ok(Box::new(SessionServiceSqlite::open(&storage.sqlite_path)?))
+        }
+    }
+}
+
 /// Wait for a shutdown signal (Ctrl+C or SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -99,42 +169,66 @@ async fn shutdown_signal() {
 
 /// Setup the application routes
 fn setup_routes(app_state: Arc<AppState>) -> Router {
-    // TODO(SeedyROM): Add rate limiting configuration
-
-    let api_routes = Router::new()
-        .route("/session", post(handlers::v1::session::create))
-        .route("/session/{session_id}", get(handlers::v1::session::get))
-        .route("/ws", get(handlers::v1::websocket::upgrade))
-        .with_state(app_state.clone());
-
-    Router::new()
-        .route("/health", get(handlers::health::get))
+    use handlers::v1::{openapi, session, websocket};
+
+    // Every route is documented from its handler's `#[utoipa::path]` as it is registered
+    // WebSocket upgrades are limited in their handler, which also holds the socket permit
+    let api_routes = OpenApiRouter::new()
+        .routes(routes!(session::list))
+        .routes(
+            routes!(session::create).layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                limits::limit_session_creates,
+            )),
+        )
+        .routes(routes!(session::get, session::end))
+        .routes(routes!(session::history))
+        .routes(routes!(session::join))
+        .routes(routes!(session::leave))
+        .routes(routes!(session::set_topic))
+        .routes(routes!(session::clear_votes))
+        .routes(routes!(session::vote))
+        .routes(routes!(session::reveal))
+        .routes(routes!(websocket::upgrade));
+
+    let (router, api_doc) = OpenApiRouter::with_openapi(openapi::ApiDoc::openapi())
+        .routes(routes!(handlers::health::get))
         .nest("/api/v1", api_routes)
-        .layer(cors_layer())
+        .split_for_parts();
+
+    #[cfg(feature = "docs-ui")]
+    let router = router.merge(
+        utoipa_swagger_ui::SwaggerUi::new(openapi::DOCS_PATH)
+            .config(utoipa_swagger_ui::Config::from(openapi::OPENAPI_PATH)),
+    );
+
+    router
+        .route(
+            openapi::OPENAPI_PATH,
+            get(move || async move { Json(api_doc) }),
+        )
+        .route(metrics::METRICS_PATH, get(handlers::metrics::get))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            metrics::track_http_requests,
+        ))
+        .layer(cors_layer(&app_state.config.cors_origins))
         .layer(tower_http::trace::TraceLayer::new_for_http())
         .with_state(app_state)
 }
 
 /// Setup CORS layer
-/// This function configures CORS settings based on the environment.
-/// In debug mode, it allows requests from specific local development ports.
-/// In production, it allows all origins (permissive).
-fn cors_layer() -> CorsLayer {
-    // if cfg!(debug_assertions) {
-    let dev_ports = vec![3000, 8000, 8080, 8081, 5173];
-    let mut origins = Vec::new();
-
-    for port in dev_ports {
-        origins.push(format!("http://localhost:{port}").parse().unwrap());
-        origins.push(format!("http://127.0.0.1:{port}").parse().unwrap());
-    }
+/// This function allows requests from the configured origins, `*` allows any origin.
+/// The origins are checked when the configuration is loaded.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().map(|origin| origin.parse().unwrap()))
+    };
 
     CorsLayer::new()
-        .allow_origin(origins)
-        .allow_headers([header::CONTENT_TYPE])
-        .allow_methods([Method::GET, Method::POST])
-    // } else {
-    //     // Production CORS - replace with specific origins
-    //     CorsLayer::permissive()
-    // }
+        .allow_origin(allow_origin)
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
 }
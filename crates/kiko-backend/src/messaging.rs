@@ -1,14 +1,20 @@
-//! A high-performance publish-subscribe messaging system for session-based communication.
+//! A sequenced publish-subscribe messaging system for session-based communication.
 //!
-//! This module provides a thread-safe, async PubSub implementation optimized for
-//! real-time messaging between WebSocket clients within sessions. It uses lock-free
-//! data structures where possible to minimize contention and maximize throughput.
+//! This module provides a thread-safe, async PubSub implementation for fanning out
+//! real-time messages to WebSocket clients within sessions. Every message published
+//! to a session is delivered to every subscriber, in order.
 //!
 //! # Architecture
 //!
 //! The PubSub system is built around two core concepts:
-//! - **Notifiers**: Per-session notification mechanisms using [`tokio::sync::Notify`]
-//! - **Events**: Per-session message storage using [`arc_swap::ArcSwap`] for lock-free reads
+//! - **Channels**: Per-session bounded broadcast logs using [`tokio::sync::broadcast`]
+//! - **Events**: Messages stamped with a per-session, monotonically increasing [`EventId`]
+//!
+//! A subscriber that falls more than [`CHANNEL_CAPACITY`] events behind loses the
+//! oldest events and is told so with [`RecvError::Lagged`]. It should then fetch a
+//! full snapshot of the session and call [`Subscription::skip_through`] with the
+//! [`PubSub::last_event_id`] read *before* the snapshot, so events the snapshot
+//! already covers aren't replayed.
 //!
 //! # Example
 //!
@@ -22,57 +28,198 @@
 //! let session_id = SessionId::new();
 //!
 //! // Subscribe to events for a session
-//! let notifier = pubsub.subscribe(session_id.clone()).await;
+//! let mut subscription = pubsub.subscribe(session_id.clone()).await;
 //!
 //! // Publish a message to the session
 //! let message = SessionMessage::CreateSession(/* ... */);
 //! pubsub.publish(session_id.clone(), message).await;
 //!
-//! // Wait for notification and consume the event
-//! notifier.notified().await;
-//! if let Some(event) = pubsub.consume_event(&session_id).await {
-//!     // Process the event
+//! // Receive the event
+//! if let Ok(event) = subscription.recv().await {
+//!     // Process event.message
 //! }
 //! # }
 //! ```
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use arc_swap::ArcSwap;
 use kiko::{data::SessionMessage, id::SessionId};
-use tokio::sync::{Notify, RwLock};
+use tokio::sync::{RwLock, broadcast};
 
 /// Event identifier type for tracking message sequence.
 ///
-/// Currently unused but reserved for future message ordering and deduplication features.
+/// Event IDs are assigned per session, start at 1 and increase by one for every
+/// published message, so a subscriber can tell exactly which events it has seen.
 pub type EventId = u64;
 
-/// A high-performance, thread-safe publish-subscribe messaging system.
+/// How many undelivered events a subscriber may fall behind before it lags.
+pub const CHANNEL_CAPACITY: usize = 64;
+
+/// A message published to a session, stamped with its sequence number.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// The position of this event in the session's sequence.
+    pub id: EventId,
+    /// The published message, shared between all subscribers.
+    pub message: Arc<SessionMessage>,
+    /// When the message was published, to tell how long delivering it took.
+    pub published_at: Instant,
+}
+
+/// Errors returned by [`Subscription::recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// The subscriber fell behind and this many events were dropped.
+    ///
+    /// The subscription remains usable and continues with the oldest retained event.
+    Lagged(u64),
+    /// The session was cleaned up and no further events will arrive.
+    Closed,
+}
+
+/// Per-session broadcast log.
+struct Channel {
+    sender: broadcast::Sender<Event>,
+    /// What was published last; the lock also keeps sends in ID order.
+    published: Mutex<Published>,
+}
+
+/// The latest event and session snapshot a channel has sent.
+#[derive(Default)]
+struct Published {
+    event_id: EventId,
+    session_version: u64,
+}
+
+impl Channel {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            published: Mutex::new(Published::default()),
+        }
+    }
+
+    fn last_event_id(&self) -> EventId {
+        self.published
+            .lock()
+            .expect("event ID lock poisoned")
+            .event_id
+    }
+}
+
+/// A subscriber's view of a session's events.
+///
+/// Created by [`PubSub::subscribe`]. Only events published after subscribing are received.
+pub struct Subscription {
+    receiver: broadcast::Receiver<Event>,
+    /// Events up to and including this ID are skipped.
+    last_seen: EventId,
+    /// An event read ahead of time, delivered by the next `recv`.
+    pending: Option<Event>,
+}
+
+impl Subscription {
+    /// Waits for the next event.
+    ///
+    /// # Errors
+    ///
+    /// * [`RecvError::Lagged`] - If the subscriber fell behind and missed events
+    /// * [`RecvError::Closed`] - If the session was cleaned up and every remaining event was received
+    pub async fn recv(&mut self) -> Result<Event, RecvError> {
+        loop {
+            let event = match self.pending.take() {
+                Some(event) => event,
+                None => match self.receiver.recv().await {
+                    Ok(event) => event,
+                    // The gap shows up in the ID of the next event
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Err(RecvError::Closed),
+                },
+            };
+
+            // Already covered by a snapshot the subscriber resynced from
+            if event.id <= self.last_seen {
+                continue;
+            }
+
+            // Events are numbered consecutively, so a jump means some were dropped
+            if event.id > self.last_seen + 1 {
+                let skipped = event.id - self.last_seen - 1;
+                self.last_seen = event.id - 1;
+                self.pending = Some(event);
+                return Err(RecvError::Lagged(skipped));
+            }
+
+            self.last_seen = event.id;
+            return Ok(event);
+        }
+    }
+
+    /// Skips every event up to and including `event_id`.
+    ///
+    /// Used after resyncing from a snapshot, so events it already reflects aren't replayed.
+    pub fn skip_through(&mut self, event_id: EventId) {
+        self.last_seen = self.last_seen.max(event_id);
+
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|event| event.id <= self.last_seen)
+        {
+            self.pending = None;
+        }
+        if self.pending.is_some() {
+            return;
+        }
+
+        // Drop retained events the snapshot covers, so they don't make the subscriber lag again
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) if event.id <= self.last_seen => continue,
+                Ok(event) => {
+                    self.pending = Some(event);
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Returns the ID of the last event received or skipped.
+    pub fn last_seen(&self) -> EventId {
+        self.last_seen
+    }
+}
+
+/// A thread-safe, sequenced publish-subscribe messaging system.
 ///
 /// `PubSub` provides session-scoped messaging capabilities with the following characteristics:
-/// - **Lock-free reads**: Uses [`ArcSwap`] for message storage to minimize read contention
-/// - **Async notifications**: Leverages [`tokio::sync::Notify`] for efficient subscriber wake-up
-/// - **Memory efficient**: Automatic cleanup prevents memory leaks from abandoned sessions
-/// - **Single message semantics**: Each session holds at most one pending message
+/// - **Every message delivered**: Subscribers receive all messages in publish order
+/// - **Sequenced**: Each message gets the next [`EventId`] for its session
+/// - **Lag detection**: Slow subscribers are told how many events they missed
+/// - **Memory efficient**: Each session retains at most [`CHANNEL_CAPACITY`] events
 ///
 /// # Thread Safety
 ///
 /// All methods are async and thread-safe. The implementation uses:
-/// - [`RwLock`] for protecting the notifier and event hashmaps
-/// - [`ArcSwap`] for lock-free atomic message updates
-/// - [`Arc<Notify>`] for efficient cross-task notifications
+/// - [`RwLock`] for protecting the channel hashmap
+/// - A per-session [`Mutex`] so event IDs are assigned and sent in order
+/// - [`broadcast`] channels for fan-out to subscribers
 ///
 /// # Performance Characteristics
 ///
 /// - **Subscribe**: O(1) amortized (HashMap insert)
-/// - **Publish**: O(1) for message update + O(log n) for notifier lookup
-/// - **Get/Consume**: O(1) lock-free read + O(log n) for HashMap access
-/// - **Cleanup**: O(1) for both notifier and event removal
+/// - **Publish**: O(1), subscribers read the shared event without copying the message
+/// - **Cleanup**: O(1), subscribers still receive events that were already published
 pub struct PubSub {
-    /// Per-session notification handles for waking up subscribers.
-    notifiers: RwLock<HashMap<SessionId, Arc<Notify>>>,
-    /// Per-session message storage using lock-free atomic swaps.
-    events: RwLock<HashMap<SessionId, ArcSwap<SessionMessage>>>,
+    /// Per-session broadcast logs.
+    channels: RwLock<HashMap<SessionId, Channel>>,
 }
 
 impl PubSub {
@@ -87,26 +234,19 @@ impl PubSub {
     /// ```
     pub fn new() -> Self {
         Self {
-            notifiers: RwLock::new(HashMap::new()),
-            events: RwLock::new(HashMap::new()),
+            channels: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Subscribes to notifications for a given session.
+    /// Subscribes to events for a given session.
     ///
-    /// Returns a [`Notify`] handle that will be signaled when new messages
-    /// are published to the session. If a notifier already exists for the session,
-    /// the same instance is returned (shared among multiple subscribers).
+    /// Returns a [`Subscription`] that receives every message published to the
+    /// session from now on. Any number of subscribers may share a session.
     ///
     /// # Arguments
     ///
     /// * `session_id` - The unique identifier of the session to subscribe to
     ///
-    /// # Returns
-    ///
-    /// An [`Arc<Notify>`] that can be awaited with `.notified().await` to
-    /// receive notifications when messages are published.
-    ///
     /// # Examples
     ///
     /// ```rust
@@ -118,34 +258,44 @@ impl PubSub {
     /// let pubsub = Arc::new(PubSub::new());
     /// let session_id = SessionId::new();
     ///
-    /// let notifier = pubsub.subscribe(session_id).await;
+    /// let mut subscription = pubsub.subscribe(session_id).await;
     ///
-    /// // Wait for the next notification
-    /// notifier.notified().await;
+    /// // Wait for the next event
+    /// let event = subscription.recv().await;
     /// # }
     /// ```
-    pub async fn subscribe(&self, session_id: SessionId) -> Arc<Notify> {
-        let mut notifiers = self.notifiers.write().await;
-        notifiers
-            .entry(session_id)
-            .or_insert_with(|| Arc::new(Notify::new()))
-            .clone()
+    pub async fn subscribe(&self, session_id: SessionId) -> Subscription {
+        let mut channels = self.channels.write().await;
+        let channel = channels.entry(session_id).or_insert_with(Channel::new);
+
+        // Hold the sequence lock so no event can be sent between reading the ID and subscribing
+        let published = channel.published.lock().expect("event ID lock poisoned");
+        Subscription {
+            receiver: channel.sender.subscribe(),
+            last_seen: published.event_id,
+            pending: None,
+        }
     }
 
     /// Publishes a message to all subscribers of a session.
     ///
-    /// If subscribers exist for the session, the message is stored and all
-    /// waiting subscribers are notified. If no subscribers exist, the message
-    /// is discarded (following typical pub/sub semantics).
+    /// If subscribers exist for the session, the message is assigned the session's
+    /// next [`EventId`] and delivered to all of them. If no subscribers exist, the
+    /// message is discarded (following typical pub/sub semantics).
     ///
-    /// **Note**: Only one message per session is stored at a time. Publishing
-    /// a new message will overwrite any existing unprocessed message.
+    /// A [`SessionMessage::SessionUpdate`] is also discarded unless its session's
+    /// [`version`](kiko::data::Session::version) is newer than the last one published,
+    /// so subscribers always end up with the latest snapshot.
     ///
     /// # Arguments
     ///
     /// * `session_id` - The session to publish the message to
     /// * `message` - The message to publish
     ///
+    /// # Returns
+    ///
+    /// The ID assigned to the event, or `None` if the message was discarded.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -158,107 +308,57 @@ impl PubSub {
     /// let session_id = SessionId::new();
     ///
     /// // First subscribe to ensure message isn't discarded
-    /// let _notifier = pubsub.subscribe(session_id.clone()).await;
+    /// let _subscription = pubsub.subscribe(session_id.clone()).await;
     ///
     /// // Then publish a message
     /// let message = SessionMessage::CreateSession(/* ... */);
     /// pubsub.publish(session_id, message).await;
     /// # }
     /// ```
-    pub async fn publish(&self, session_id: SessionId, message: SessionMessage) {
-        // Get the notifier first
-        let notifier = self.notifiers.read().await.get(&session_id).cloned();
-
-        if let Some(notifier) = notifier {
-            // Store the message and immediately drop the write lock
-            {
-                let mut events = self.events.write().await;
-                events.insert(session_id, ArcSwap::from_pointee(message));
-            } // Write lock dropped here
-
-            // Now notify waiters - they can immediately acquire read locks
-            notifier.notify_waiters();
+    pub async fn publish(&self, session_id: SessionId, message: SessionMessage) -> Option<EventId> {
+        let channels = self.channels.read().await;
+        let channel = channels.get(&session_id)?;
+
+        let mut published = channel.published.lock().expect("event ID lock poisoned");
+
+        // Changes are stored and published separately, so a snapshot can be overtaken
+        // by a newer one. Sending it late would roll every subscriber back.
+        if let SessionMessage::SessionUpdate(session) = &message {
+            if session.version() <= published.session_version {
+                return None;
+            }
+            published.session_version = session.version();
         }
-    }
 
-    /// Retrieves the current message for a session without removing it.
-    ///
-    /// This method performs a lock-free read of the message using [`ArcSwap::load_full`].
-    /// The message remains available for subsequent calls to [`get_event`] or [`consume_event`].
-    ///
-    /// # Arguments
-    ///
-    /// * `session_id` - The session ID to retrieve the message for
-    ///
-    /// # Returns
-    ///
-    /// * `Some(Arc<SessionMessage>)` - If a message exists for the session
-    /// * `None` - If no message exists for the session
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use kiko_backend::messaging::PubSub;
-    /// use kiko::id::SessionId;
-    ///
-    /// # async fn example() {
-    /// let pubsub = PubSub::new();
-    /// let session_id = SessionId::new();
-    ///
-    /// // Check if there's a message (non-destructive)
-    /// if let Some(message) = pubsub.get_event(&session_id).await {
-    ///     // Process message, but it remains in the queue
-    /// }
-    /// # }
-    /// ```
-    pub async fn get_event(&self, session_id: &SessionId) -> Option<Arc<SessionMessage>> {
-        let events = self.events.read().await;
-        events.get(session_id).map(|arc_swap| arc_swap.load_full())
+        published.event_id += 1;
+        let event = Event {
+            id: published.event_id,
+            message: Arc::new(message),
+            published_at: Instant::now(),
+        };
+
+        // Sending only fails when every subscriber has gone away, which is fine
+        let _ = channel.sender.send(event);
+        Some(published.event_id)
     }
 
-    /// Consumes and removes the current message for a session.
-    ///
-    /// This method retrieves the message and removes it from storage in a single
-    /// atomic operation. Subsequent calls will return `None` until a new message
-    /// is published.
+    /// Returns the ID of the last event published to a session.
     ///
-    /// # Arguments
-    ///
-    /// * `session_id` - The session ID to consume the message for
-    ///
-    /// # Returns
-    ///
-    /// * `Some(Arc<SessionMessage>)` - If a message existed and was consumed
-    /// * `None` - If no message existed for the session
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use kiko_backend::messaging::PubSub;
-    /// use kiko::id::SessionId;
-    ///
-    /// # async fn example() {
-    /// let pubsub = PubSub::new();
-    /// let session_id = SessionId::new();
-    ///
-    /// // Consume the message (removes it from queue)
-    /// if let Some(message) = pubsub.consume_event(&session_id).await {
-    ///     // Process message - it's no longer in the queue
-    /// }
-    /// # }
-    /// ```
-    pub async fn consume_event(&self, session_id: &SessionId) -> Option<Arc<SessionMessage>> {
-        let mut events = self.events.write().await;
-        events
-            .remove(session_id)
-            .map(|arc_swap| arc_swap.load_full())
+    /// Returns 0 if nothing has been published yet, or if nobody has subscribed to the session.
+    pub async fn last_event_id(&self, session_id: &SessionId) -> EventId {
+        self.channels
+            .read()
+            .await
+            .get(session_id)
+            .map(Channel::last_event_id)
+            .unwrap_or(0)
     }
 
     /// Completely removes all data associated with a session.
     ///
-    /// This method removes both the notifier and any pending messages for the session,
-    /// effectively cleaning up all resources. This should be called when a session
-    /// ends to prevent memory leaks.
+    /// Existing subscriptions still receive the events that were already published
+    /// and then get [`RecvError::Closed`]. This should be called when a session ends
+    /// to prevent memory leaks.
     ///
     /// # Arguments
     ///
@@ -279,20 +379,14 @@ impl PubSub {
     /// # }
     /// ```
     pub async fn cleanup_session(&self, session_id: &SessionId) {
-        let mut events = self.events.write().await;
-        let mut notifiers = self.notifiers.write().await;
-        events.remove(session_id);
-        notifiers.remove(session_id);
+        self.channels.write().await.remove(session_id);
     }
 
-    /// Returns the number of active sessions with subscribers.
-    ///
-    /// This count represents sessions that have at least one subscriber,
-    /// regardless of whether they have pending messages.
+    /// Returns the number of sessions that have been subscribed to.
     ///
     /// # Returns
     ///
-    /// The number of sessions with active subscribers.
+    /// The number of sessions with a channel.
     ///
     /// # Examples
     ///
@@ -306,49 +400,23 @@ impl PubSub {
     /// assert_eq!(pubsub.session_count().await, 0);
     ///
     /// let session_id = SessionId::new();
-    /// let _notifier = pubsub.subscribe(session_id).await;
+    /// let _subscription = pubsub.subscribe(session_id).await;
     ///
     /// assert_eq!(pubsub.session_count().await, 1);
     /// # }
     /// ```
     pub async fn session_count(&self) -> usize {
-        self.notifiers.read().await.len()
+        self.channels.read().await.len()
     }
 
-    /// Checks if a session has a pending message without retrieving it.
-    ///
-    /// This is a non-destructive way to check for message availability,
-    /// useful for conditional processing logic.
-    ///
-    /// # Arguments
-    ///
-    /// * `session_id` - The session ID to check
-    ///
-    /// # Returns
-    ///
-    /// * `true` - If the session has a pending message
-    /// * `false` - If the session has no pending message
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use kiko_backend::messaging::PubSub;
-    /// use kiko::id::SessionId;
-    ///
-    /// # async fn example() {
-    /// let pubsub = PubSub::new();
-    /// let session_id = SessionId::new();
-    ///
-    /// // Check before processing
-    /// if pubsub.has_event(&session_id).await {
-    ///     let message = pubsub.consume_event(&session_id).await;
-    ///     // Process the message
-    /// }
-    /// # }
-    /// ```
-    pub async fn has_event(&self, session_id: &SessionId) -> bool {
-        let events = self.events.read().await;
-        events.contains_key(session_id)
+    /// Returns the number of live subscriptions to a session.
+    pub async fn subscriber_count(&self, session_id: &SessionId) -> usize {
+        self.channels
+            .read()
+            .await
+            .get(session_id)
+            .map(|channel| channel.sender.receiver_count())
+            .unwrap_or(0)
     }
 }
 
@@ -367,122 +435,272 @@ mod tests {
     use kiko::data::SessionMessage;
     use kiko::id::SessionId;
     use std::time::Duration;
-    use tokio::time::{sleep, timeout};
+    use tokio::time::timeout;
+
+    fn create_message(name: &str) -> SessionMessage {
+        SessionMessage::CreateSession(kiko::data::CreateSession {
+            name: name.to_string(),
+            duration: Duration::from_secs(1800),
+            deck: kiko::data::Deck::default(),
+        })
+    }
+
+    fn message_name(event: &Event) -> &str {
+        match event.message.as_ref() {
+            SessionMessage::CreateSession(create) => &create.name,
+            _ => panic!("Unexpected message type"),
+        }
+    }
 
     #[tokio::test]
-    async fn consume_event_removes_message() {
+    async fn delivers_every_message_in_order() {
         let pubsub = PubSub::new();
         let session_id = SessionId::new();
 
-        let _notifier = pubsub.subscribe(session_id.clone()).await;
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
 
-        let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-            name: "Test Message".to_string(),
-            duration: Duration::from_secs(1800),
+        // Publish a burst without the subscriber getting a chance to run
+        for i in 0..10 {
+            pubsub
+                .publish(session_id.clone(), create_message(&format!("Message {i}")))
+                .await;
+        }
+
+        for i in 0..10 {
+            let event = subscription.recv().await.unwrap();
+            assert_eq!(event.id, i + 1);
+            assert_eq!(message_name(&event), format!("Message {i}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_session_snapshots_are_dropped() {
+        let pubsub = PubSub::new();
+        let session_id = SessionId::new();
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
+
+        let mut session = kiko::data::Session::new(
+            "Snapshots".to_string(),
+            Duration::from_secs(1800),
+            kiko::data::Deck::default(),
+        );
+        session.next_version();
+        let older = SessionMessage::SessionUpdate(Box::new(session.clone()));
+        session.next_version();
+        let newer = SessionMessage::SessionUpdate(Box::new(session));
+
+        // The newer snapshot overtook the older one on its way here
+        assert_eq!(pubsub.publish(session_id.clone(), newer).await, Some(1));
+        assert_eq!(pubsub.publish(session_id.clone(), older).await, None);
+
+        let event = subscription.recv().await.unwrap();
+        assert!(matches!(
+            event.message.as_ref(),
+            SessionMessage::SessionUpdate(update) if update.version() == 2
+        ));
+        assert_eq!(pubsub.last_event_id(&session_id).await, 1);
+    }
+
+    #[tokio::test]
+    async fn event_ids_are_per_session() {
+        let pubsub = PubSub::new();
+        let first = SessionId::new();
+        let second = SessionId::new();
+
+        let _first_subscription = pubsub.subscribe(first.clone()).await;
+        let _second_subscription = pubsub.subscribe(second.clone()).await;
+
+        assert_eq!(
+            pubsub.publish(first.clone(), create_message("a")).await,
+            Some(1)
+        );
+        assert_eq!(
+            pubsub.publish(first.clone(), create_message("b")).await,
+            Some(2)
+        );
+        assert_eq!(
+            pubsub.publish(second.clone(), create_message("c")).await,
+            Some(1)
+        );
+
+        assert_eq!(pubsub.last_event_id(&first).await, 2);
+        assert_eq!(pubsub.last_event_id(&second).await, 1);
+    }
+
+    #[tokio::test]
+    async fn multiple_subscribers() {
+        let pubsub = PubSub::new();
+        let session_id = SessionId::new();
+        let mut subscription1 = pubsub.subscribe(session_id.clone()).await;
+        let mut subscription2 = pubsub.subscribe(session_id.clone()).await;
+
+        let message = SessionMessage::AddParticipant(kiko::data::AddParticipant {
+            session_id: session_id.clone().to_string(),
+            participant_name: "participant1".to_string(),
         });
 
         pubsub.publish(session_id.clone(), message).await;
 
-        // First consume should return the message
-        let event = pubsub.consume_event(&session_id).await;
-        assert!(event.is_some());
+        for subscription in [&mut subscription1, &mut subscription2] {
+            let event = subscription.recv().await.unwrap();
+            match event.message.as_ref() {
+                SessionMessage::AddParticipant(add_participant) => {
+                    assert_eq!(add_participant.participant_name, "participant1");
+                }
+                _ => panic!("Unexpected message type"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn correct_pubsub_behavior_subscribe_after_publish() {
+        let pubsub = PubSub::new();
+        let session_id = SessionId::new();
 
-        // Second consume should return None (message was removed)
-        let event2 = pubsub.consume_event(&session_id).await;
-        assert!(event2.is_none());
+        // Publish first (no subscribers yet) - the message is discarded
+        assert_eq!(
+            pubsub
+                .publish(session_id.clone(), create_message("Early Message"))
+                .await,
+            None
+        );
 
-        // get_event should also return None now
-        let event3 = pubsub.get_event(&session_id).await;
-        assert!(event3.is_none());
+        // Then subscribe
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
+
+        // Message should not be available since we subscribed after publishing
+        let event = timeout(Duration::from_millis(50), subscription.recv()).await;
+        assert!(
+            event.is_err(),
+            "Should not receive messages published before subscription"
+        );
     }
 
     #[tokio::test]
-    async fn get_event_vs_consume_event() {
+    async fn late_subscriber_only_sees_new_events() {
         let pubsub = PubSub::new();
         let session_id = SessionId::new();
 
-        let _notifier = pubsub.subscribe(session_id.clone()).await;
+        let _early = pubsub.subscribe(session_id.clone()).await;
+        pubsub
+            .publish(session_id.clone(), create_message("Before"))
+            .await;
 
-        let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-            name: "Persistent Test".to_string(),
-            duration: Duration::from_secs(1800),
-        });
+        let mut late = pubsub.subscribe(session_id.clone()).await;
+        pubsub
+            .publish(session_id.clone(), create_message("After"))
+            .await;
 
-        pubsub.publish(session_id.clone(), message).await;
+        let event = late.recv().await.unwrap();
+        assert_eq!(event.id, 2);
+        assert_eq!(message_name(&event), "After");
+    }
+
+    #[tokio::test]
+    async fn slow_subscriber_is_told_it_lagged() {
+        let pubsub = PubSub::new();
+        let session_id = SessionId::new();
 
-        // get_event should not remove the message
-        let event1 = pubsub.get_event(&session_id).await;
-        assert!(event1.is_some());
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
 
-        let event2 = pubsub.get_event(&session_id).await;
-        assert!(event2.is_some());
+        let total = CHANNEL_CAPACITY as u64 + 10;
+        for i in 0..total {
+            pubsub
+                .publish(session_id.clone(), create_message(&format!("Message {i}")))
+                .await;
+        }
 
-        // consume_event should remove it
-        let event3 = pubsub.consume_event(&session_id).await;
-        assert!(event3.is_some());
+        assert_eq!(
+            subscription.recv().await.unwrap_err(),
+            RecvError::Lagged(10)
+        );
 
-        // Now it should be gone
-        let event4 = pubsub.get_event(&session_id).await;
-        assert!(event4.is_none());
+        // The subscription carries on with the oldest retained event
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(event.id, 11);
     }
 
     #[tokio::test]
-    async fn cleanup_session() {
+    async fn skip_through_drops_events_covered_by_a_snapshot() {
         let pubsub = PubSub::new();
         let session_id = SessionId::new();
 
-        let _notifier = pubsub.subscribe(session_id.clone()).await;
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
+        for i in 0..CHANNEL_CAPACITY + 5 {
+            pubsub
+                .publish(session_id.clone(), create_message(&format!("Message {i}")))
+                .await;
+        }
 
-        let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-            name: "Cleanup Test".to_string(),
-            duration: Duration::from_secs(1800),
-        });
+        assert!(matches!(
+            subscription.recv().await,
+            Err(RecvError::Lagged(_))
+        ));
+
+        // Resync: everything published so far is reflected in the snapshot
+        let resync_id = pubsub.last_event_id(&session_id).await;
+        subscription.skip_through(resync_id);
+
+        pubsub
+            .publish(session_id.clone(), create_message("Fresh"))
+            .await;
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(event.id, resync_id + 1);
+        assert_eq!(message_name(&event), "Fresh");
+    }
 
-        pubsub.publish(session_id.clone(), message).await;
+    #[tokio::test]
+    async fn cleanup_session() {
+        let pubsub = PubSub::new();
+        let session_id = SessionId::new();
+
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
+        pubsub
+            .publish(session_id.clone(), create_message("Cleanup Test"))
+            .await;
 
         // Verify session exists
         assert_eq!(pubsub.session_count().await, 1);
-        assert!(pubsub.has_event(&session_id).await);
+        assert_eq!(pubsub.subscriber_count(&session_id).await, 1);
 
         // Cleanup session
         pubsub.cleanup_session(&session_id).await;
 
         // Verify session is gone
         assert_eq!(pubsub.session_count().await, 0);
-        assert!(!pubsub.has_event(&session_id).await);
+        assert_eq!(pubsub.last_event_id(&session_id).await, 0);
 
-        let event = pubsub.get_event(&session_id).await;
-        assert!(event.is_none());
+        // Already published events are still delivered, then the subscription closes
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(message_name(&event), "Cleanup Test");
+        assert_eq!(subscription.recv().await.unwrap_err(), RecvError::Closed);
     }
 
     #[tokio::test]
     async fn memory_leak_prevention() {
         let pubsub = PubSub::new();
         let mut session_ids = Vec::new();
+        let mut subscriptions = Vec::new();
 
         // Create many sessions
         for i in 0..100 {
             let session_id = SessionId::new();
             session_ids.push(session_id.clone());
 
-            let _notifier = pubsub.subscribe(session_id.clone()).await;
-
-            let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-                name: format!("Session {i}"),
-                duration: Duration::from_secs(1800),
-            });
-            pubsub.publish(session_id, message).await;
+            subscriptions.push(pubsub.subscribe(session_id.clone()).await);
+            pubsub
+                .publish(session_id, create_message(&format!("Session {i}")))
+                .await;
         }
 
         assert_eq!(pubsub.session_count().await, 100);
 
-        // Consume all events
-        for session_id in &session_ids {
-            let event = pubsub.consume_event(session_id).await;
-            assert!(event.is_some());
+        // Receive all events
+        for subscription in &mut subscriptions {
+            assert!(subscription.recv().await.is_ok());
         }
 
-        // Events should be removed, but notifiers should still exist
+        // Channels remain until the sessions are cleaned up
         assert_eq!(pubsub.session_count().await, 100);
 
         // Clean up all sessions
@@ -494,63 +712,11 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn has_event_utility() {
-        let pubsub = PubSub::new();
-        let session_id = SessionId::new();
-
-        let _notifier = pubsub.subscribe(session_id.clone()).await;
-
-        // No event initially
-        assert!(!pubsub.has_event(&session_id).await);
-
-        // Publish message
-        let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-            name: "Event Check".to_string(),
-            duration: Duration::from_secs(1800),
-        });
-        pubsub.publish(session_id.clone(), message).await;
-
-        // Should have event now
-        assert!(pubsub.has_event(&session_id).await);
-
-        // Consume event
-        let _event = pubsub.consume_event(&session_id).await;
-
-        // Should not have event anymore
-        assert!(!pubsub.has_event(&session_id).await);
-    }
-
-    #[tokio::test]
-    async fn correct_pubsub_behavior_subscribe_after_publish() {
-        let pubsub = PubSub::new();
-        let session_id = SessionId::new();
-
-        // Publish first (no subscribers yet) - this is correct behavior
-        let message = SessionMessage::CreateSession(kiko::data::CreateSession {
-            name: "Early Message".to_string(),
-            duration: Duration::from_secs(1800),
-        });
-
-        pubsub.publish(session_id.clone(), message).await;
-
-        // Then subscribe
-        let _notifier = pubsub.subscribe(session_id.clone()).await;
-
-        // Message should not be available since we subscribed after publishing
-        // This is CORRECT pub/sub behavior
-        let event = pubsub.get_event(&session_id).await;
-        assert!(
-            event.is_none(),
-            "Should not receive messages published before subscription"
-        );
-    }
-
-    #[tokio::test]
-    async fn event_processing_loop_with_consume() {
+    async fn event_processing_loop_with_concurrent_publisher() {
         let pubsub = Arc::new(PubSub::new());
         let session_id = SessionId::new();
 
-        let notifier = pubsub.subscribe(session_id.clone()).await;
+        let mut subscription = pubsub.subscribe(session_id.clone()).await;
 
         let pubsub_clone = pubsub.clone();
         let session_id_clone = session_id.clone();
@@ -565,76 +731,27 @@ mod tests {
                 pubsub_clone
                     .publish(session_id_clone.clone(), message)
                     .await;
-                sleep(Duration::from_millis(50)).await;
             }
         });
 
-        // Consumer loop - this will now work correctly with consume_event
-        let mut processed_count = 0;
-        let max_messages = 3;
-
-        while processed_count < max_messages {
-            // Wait for notification
-            let notification_result =
-                timeout(Duration::from_millis(200), notifier.notified()).await;
-            if notification_result.is_ok() {
-                // Process the event
-                if let Some(event) = pubsub.consume_event(&session_id).await {
-                    match event.as_ref() {
-                        SessionMessage::RemoveParticipant(remove_participant) => {
-                            println!(
-                                "Processed participant: {}",
-                                remove_participant.participant_id
-                            );
-                            processed_count += 1;
-                        }
-                        _ => panic!("Unexpected message type"),
-                    }
+        let mut processed = Vec::new();
+        while processed.len() < 3 {
+            let event = timeout(Duration::from_millis(200), subscription.recv())
+                .await
+                .expect("Timed out waiting for event")
+                .unwrap();
+            match event.message.as_ref() {
+                SessionMessage::RemoveParticipant(remove_participant) => {
+                    processed.push(remove_participant.participant_id.clone());
                 }
-            } else {
-                break; // Timeout
+                _ => panic!("Unexpected message type"),
             }
         }
 
         publisher.await.unwrap();
-        assert_eq!(processed_count, 3);
-
-        // All events should be consumed
-        assert!(!pubsub.has_event(&session_id).await);
-    }
-
-    // Keep your original working tests too
-    #[tokio::test]
-    async fn multiple_subscribers() {
-        let pubsub = PubSub::new();
-        let session_id = SessionId::new();
-        let _notifier1 = pubsub.subscribe(session_id.clone()).await;
-        let _notifier2 = pubsub.subscribe(session_id.clone()).await;
-
-        let message = SessionMessage::AddParticipant(kiko::data::AddParticipant {
-            session_id: session_id.clone().to_string(),
-            participant_name: "participant1".to_string(),
-        });
-
-        pubsub.publish(session_id.clone(), message.clone()).await;
-        let event1 = pubsub.get_event(&session_id).await;
-        let event2 = pubsub.get_event(&session_id).await;
-
-        assert!(event1.is_some());
-        assert!(event2.is_some());
-
-        match event1.unwrap().as_ref() {
-            SessionMessage::AddParticipant(add_participant) => {
-                assert_eq!(add_participant.participant_name, "participant1");
-            }
-            _ => panic!("Unexpected message type"),
-        }
-
-        match event2.unwrap().as_ref() {
-            SessionMessage::AddParticipant(add_participant) => {
-                assert_eq!(add_participant.participant_name, "participant1");
-            }
-            _ => panic!("Unexpected message type"),
-        }
+        assert_eq!(
+            processed,
+            vec!["participant_0", "participant_1", "participant_2"]
+        );
     }
 }
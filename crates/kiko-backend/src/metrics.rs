@@ -0,0 +1,224 @@
+//! Prometheus metrics, served in the OpenMetrics text format at [`METRICS_PATH`].
+//!
+//! Counters and histograms are updated as things happen, while the number of
+//! active sessions and participants is read from storage on every scrape.
+
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus_client::{
+    encoding::{EncodeLabelSet, text::encode},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{Histogram, exponential_buckets},
+    },
+    registry::{Registry, Unit},
+};
+
+use kiko::data::Session;
+
+/// Where Prometheus scrapes the metrics from.
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The content type of [`Metrics::encode`]'s output.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct MessageLabels {
+    kind: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct HttpLabels {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+/// Every metric the backend exposes.
+pub struct Metrics {
+    registry: Registry,
+    sessions: Gauge,
+    participants: Gauge,
+    websockets: Gauge,
+    messages: Family<MessageLabels, Counter>,
+    fan_out_latency: Histogram,
+    http_durations: Family<HttpLabels, Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("kiko");
+
+        let sessions = Gauge::default();
+        registry.register("sessions", "Sessions currently running", sessions.clone());
+
+        let participants = Gauge::default();
+        registry.register(
+            "participants",
+            "Participants in all running sessions",
+            participants.clone(),
+        );
+
+        let websockets = Gauge::default();
+        registry.register(
+            "websockets",
+            "WebSocket connections currently open",
+            websockets.clone(),
+        );
+
+        let messages = Family::<MessageLabels, Counter>::default();
+        registry.register(
+            "websocket_messages",
+            "Session messages received over WebSockets, by kind",
+            messages.clone(),
+        );
+
+        // From 0.1ms up to about 200ms
+        let fan_out_latency = Histogram::new(exponential_buckets(0.0001, 2.0, 12));
+        registry.register_with_unit(
+            "pubsub_fan_out_latency",
+            "Time from publishing an event until a subscriber receives it",
+            Unit::Seconds,
+            fan_out_latency.clone(),
+        );
+
+        // From 1ms up to about 4s
+        let http_durations = Family::<HttpLabels, Histogram>::new_with_constructor(
+            (|| Histogram::new(exponential_buckets(0.001, 2.0, 13))) as fn() -> Histogram,
+        );
+        registry.register_with_unit(
+            "http_request_duration",
+            "Time taken to answer HTTP requests, by route and status",
+            Unit::Seconds,
+            http_durations.clone(),
+        );
+
+        Self {
+            registry,
+            sessions,
+            participants,
+            websockets,
+            messages,
+            fan_out_latency,
+            http_durations,
+        }
+    }
+
+    /// Counts a message received from a client.
+    pub fn message_received(&self, kind: &'static str) {
+        self.messages.get_or_create(&MessageLabels { kind }).inc();
+    }
+
+    /// Records how long an event took to reach a subscriber.
+    pub fn event_delivered(&self, published_at: Instant) {
+        self.fan_out_latency
+            .observe(published_at.elapsed().as_secs_f64());
+    }
+
+    /// Counts an open WebSocket until the returned guard is dropped.
+    pub fn websocket_opened(&self) -> OpenWebSocket {
+        self.websockets.inc();
+        OpenWebSocket(self.websockets.clone())
+    }
+
+    /// Renders every metric, counting the given sessions as the ones running.
+    pub fn encode(&self, sessions: &[Session]) -> String {
+        self.sessions.set(sessions.len() as i64);
+        self.participants.set(
+            sessions
+                .iter()
+                .map(|session| session.participants().len() as i64)
+                .sum(),
+        );
+
+        let mut output = String::new();
+        encode(&mut output, &self.registry).expect("writing to a String can't fail");
+        output
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One of the open WebSockets, no longer counted once dropped.
+#[derive(Debug)]
+pub struct OpenWebSocket(Gauge);
+
+impl Drop for OpenWebSocket {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Middleware timing every request by the route it matched.
+///
+/// Must be added with `route_layer`, so the route is known and requests for
+/// unknown paths can't create new label values.
+pub async fn track_http_requests(
+    State(state): State<Arc<crate::AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+
+    let response = next.run(request).await;
+
+    let labels = HttpLabels {
+        method,
+        route,
+        status: response.status().as_u16(),
+    };
+    state
+        .metrics
+        .http_durations
+        .get_or_create(&labels)
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_websockets_are_counted_until_dropped() {
+        let metrics = Metrics::new();
+
+        let first = metrics.websocket_opened();
+        let _second = metrics.websocket_opened();
+        assert_eq!(metrics.websockets.get(), 2);
+
+        drop(first);
+        assert_eq!(metrics.websockets.get(), 1);
+    }
+
+    #[test]
+    fn messages_are_counted_by_kind() {
+        let metrics = Metrics::new();
+        metrics.message_received("ClearPoints");
+        metrics.message_received("ClearPoints");
+        metrics.message_received("NextStory");
+
+        let output = metrics.encode(&[]);
+        assert!(output.contains(r#"kiko_websocket_messages_total{kind="ClearPoints"} 2"#));
+        assert!(output.contains(r#"kiko_websocket_messages_total{kind="NextStory"} 1"#));
+        assert!(output.contains("kiko_sessions 0"));
+        assert!(output.ends_with("# EOF\n"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Background cleanup of finished sessions.
+//!
+//! Most sessions are never ended by their facilitator, so without this task the
+//! session store would grow forever. The reaper periodically sweeps all sessions
+//! and ends the ones that are past their duration, or that have had nobody in
+//! them for longer than the idle timeout.
+//!
+//! Sessions are ended with [`end_session`](crate::actions::end_session), which
+//! checks again that the session is expired or empty while it is removed from the
+//! [`SessionService`](crate::services::SessionService), then sends connected clients
+//! a [`SessionEnded`](kiko::data::SessionMessage::SessionEnded) message.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use kiko::{
+    data::{Session, SessionEndReason},
+    errors::Report,
+    id::SessionId,
+    log,
+};
+
+use crate::services::{SessionCheck, SessionServiceError};
+
+/// How often the reaper sweeps for finished sessions.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a session may sit without participants before it is ended.
+pub const EMPTY_SESSION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Tracks session state between sweeps.
+pub struct SessionReaper {
+    /// How long a session may stay empty before it is ended.
+    idle_timeout: Duration,
+    /// When each currently empty session was first seen empty.
+    empty_since: HashMap<SessionId, Instant>,
+}
+
+impl SessionReaper {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            empty_since: HashMap::new(),
+        }
+    }
+
+    /// Runs a single sweep over all sessions.
+    ///
+    /// Returns the IDs of the sessions that were ended during this sweep.
+    pub async fn sweep(&mut self, state: &crate::AppState) -> Vec<SessionId> {
+        let sessions = match state.sessions.list().await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                log::error!("Failed to list sessions for reaping: {}", e);
+                return Vec::new();
+            }
+        };
+
+        // Forget about sessions that were ended elsewhere
+        let live: HashSet<&SessionId> = sessions.iter().map(|s| &s.id).collect();
+        self.empty_since.retain(|id, _| live.contains(id));
+
+        let now = Instant::now();
+        let mut ended = Vec::new();
+
+        for session in &sessions {
+            let reason = if !session.is_active() {
+                Some(SessionEndReason::Expired)
+            } else if session.participants().is_empty() {
+                let since = self.empty_since.entry(session.id.clone()).or_insert(now);
+                (now.duration_since(*since) >= self.idle_timeout)
+                    .then_some(SessionEndReason::Abandoned)
+            } else {
+                self.empty_since.remove(&session.id);
+                None
+            };
+
+            let Some(reason) = reason else {
+                continue;
+            };
+
+            // The listing may be stale by now, so check again before removing the session
+            let check = still_reapable(&reason);
+            match crate::actions::end_session(state, &session.id, reason, check).await {
+                Ok(()) => {}
+                // Someone joined or the session ended elsewhere since it was listed
+                Err(SessionServiceError::Rejected(_) | SessionServiceError::NotFound(_)) => {
+                    self.empty_since.remove(&session.id);
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("Failed to end session {:?}: {}", session.id, e);
+                    continue;
+                }
+            }
+
+            self.empty_since.remove(&session.id);
+            ended.push(session.id.clone());
+        }
+
+        ended
+    }
+}
+
+/// Keeps the session unless the reason it was picked for still holds.
+fn still_reapable(reason: &SessionEndReason) -> SessionCheck<Session, SessionServiceError> {
+    let expired = matches!(reason, SessionEndReason::Expired);
+    Box::new(move |session| {
+        if expired && session.is_active() {
+            Err(SessionServiceError::Rejected(Report::msg(
+                "Session is no longer expired",
+            )))
+        } else if !expired && !session.participants().is_empty() {
+            Err(SessionServiceError::Rejected(Report::msg(
+                "Session is no longer empty",
+            )))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Spawns the reaper as a background task that sweeps every `interval`.
+pub fn spawn(
+    state: Arc<crate::AppState>,
+    interval: Duration,
+    idle_timeout: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut reaper = SessionReaper::new(idle_timeout);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            let ended = reaper.sweep(&state).await;
+            if !ended.is_empty() {
+                log::info!("Reaper ended {} session(s)", ended.len());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kiko::data::{CreateSession, Deck, SessionMessage};
+
+    fn app_state() -> crate::AppState {
+        crate::AppState::in_memory()
+    }
+
+    async fn create_session(state: &crate::AppState, duration: Duration) -> SessionId {
+        state
+            .sessions
+            .create(CreateSession {
+                name: "Reaper Test".to_string(),
+                duration,
+                deck: Deck::default(),
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    /// Backdates a session's start, so it has already run out.
+    async fn expire(state: &crate::AppState, session_id: &SessionId) {
+        let session = state.sessions.get(session_id).await.unwrap();
+        let mut value = kiko::serde_json::to_value(&session).unwrap();
+        value["started"] = 0.into();
+        let session = kiko::serde_json::from_value(value).unwrap();
+        state.sessions.update(session_id, &session).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ends_expired_sessions_and_notifies_subscribers() {
+        let state = app_state();
+        let expired = create_session(&state, Duration::from_secs(3600)).await;
+        expire(&state, &expired).await;
+        let mut subscription = state.pub_sub.subscribe(expired.clone()).await;
+
+        let mut reaper = SessionReaper::new(EMPTY_SESSION_TIMEOUT);
+        let ended = reaper.sweep(&state).await;
+
+        assert_eq!(ended, vec![expired.clone()]);
+        assert!(state.sessions.get(&expired).await.is_err());
+        assert_eq!(state.pub_sub.session_count().await, 0);
+
+        // Subscribers still get the final message after the channel is cleaned up
+        let event = subscription.recv().await.unwrap();
+        assert!(matches!(
+            event.message.as_ref(),
+            SessionMessage::SessionEnded(SessionEndReason::Expired)
+        ));
+        assert!(subscription.recv().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ends_sessions_that_stay_empty() {
+        let state = app_state();
+        let session_id = create_session(&state, Duration::from_secs(3600)).await;
+
+        let mut reaper = SessionReaper::new(Duration::from_millis(50));
+
+        // First sweep only starts the idle clock
+        assert!(reaper.sweep(&state).await.is_empty());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(reaper.sweep(&state).await, vec![session_id.clone()]);
+        assert!(state.sessions.get(&session_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_active_sessions_with_participants() {
+        let state = app_state();
+        let session_id = create_session(&state, Duration::from_secs(3600)).await;
+        state.sessions.join(&session_id, "Alex").await.unwrap();
+
+        let mut reaper = SessionReaper::new(Duration::ZERO);
+        assert!(reaper.sweep(&state).await.is_empty());
+        assert!(state.sessions.get(&session_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn keeps_sessions_joined_after_they_were_listed() {
+        let state = app_state();
+        let session_id = create_session(&state, Duration::from_secs(3600)).await;
+
+        // The sweep saw the session empty, but someone joins before it is ended
+        state.sessions.join(&session_id, "Alex").await.unwrap();
+        let check = still_reapable(&SessionEndReason::Abandoned);
+        let result =
+            crate::actions::end_session(&state, &session_id, SessionEndReason::Abandoned, check)
+                .await;
+
+        assert!(matches!(result, Err(SessionServiceError::Rejected(_))));
+        assert!(state.sessions.get(&session_id).await.is_ok());
+    }
+}
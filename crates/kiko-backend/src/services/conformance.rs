@@ -0,0 +1,326 @@
+//! Conformance tests every `SessionService` implementation must pass.
+//!
+//! Each check is written once against the trait, and `conformance_tests!` stamps
+//! out a test module per implementation so the backends can't drift apart.
+
+use std::{sync::Arc, time::Duration};
+
+use kiko::{
+    data::{CreateSession, Deck, MAX_PARTICIPANTS, Story},
+    errors::Report,
+    id::{ParticipantId, SessionId, StoryId},
+};
+
+use super::{SessionService, SessionServiceError};
+
+type Service = dyn SessionService<Error = SessionServiceError> + Send + Sync;
+
+fn create_request(name: &str) -> CreateSession {
+    CreateSession {
+        name: name.to_string(),
+        duration: Duration::from_secs(3600),
+        deck: Deck::ModifiedFibonacci,
+    }
+}
+
+pub async fn create_then_get(service: Arc<Service>) {
+    let created = service.create(create_request("Planning")).await.unwrap();
+    let fetched = service.get(&created.id).await.unwrap();
+
+    assert_eq!(fetched.id, created.id);
+    assert_eq!(fetched.name(), "Planning");
+    assert_eq!(fetched.deck(), &Deck::ModifiedFibonacci);
+    assert_eq!(fetched.duration(), Duration::from_secs(3600));
+    assert!(fetched.participants().is_empty());
+}
+
+pub async fn get_missing_session_fails(service: Arc<Service>) {
+    assert!(matches!(
+        service.get(&SessionId::new()).await,
+        Err(SessionServiceError::NotFound(_))
+    ));
+}
+
+pub async fn create_rejects_invalid_requests(service: Arc<Service>) {
+    let result = service.create(create_request("   ")).await;
+    assert!(matches!(result, Err(SessionServiceError::Validation(_))));
+
+    let result = service
+        .create(CreateSession {
+            deck: Deck::Custom(Vec::new()),
+            ..create_request("Planning")
+        })
+        .await;
+    assert!(matches!(result, Err(SessionServiceError::Validation(_))));
+    assert!(service.list().await.unwrap().is_empty());
+}
+
+pub async fn list_returns_all_sessions(service: Arc<Service>) {
+    assert!(service.list().await.unwrap().is_empty());
+
+    let first = service.create(create_request("First")).await.unwrap();
+    let second = service.create(create_request("Second")).await.unwrap();
+
+    let ids: Vec<SessionId> = service
+        .list()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&first.id));
+    assert!(ids.contains(&second.id));
+}
+
+pub async fn update_persists_changes(service: Arc<Service>) {
+    let mut session = service.create(create_request("Planning")).await.unwrap();
+    session.set_topic("Login page".to_string());
+    session.add_story(Story::new(StoryId::new(), "Signup flow".to_string()));
+
+    service.update(&session.id, &session).await.unwrap();
+
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.current_topic(), "Login page");
+    assert_eq!(fetched.stories().len(), 1);
+    assert_eq!(fetched.stories()[0].title(), "Signup flow");
+}
+
+pub async fn update_missing_session_fails(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+    assert!(matches!(
+        service.update(&SessionId::new(), &session).await,
+        Err(SessionServiceError::NotFound(_))
+    ));
+}
+
+pub async fn join_adds_participants(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+
+    let alex = service.join(&session.id, "Alex").await.unwrap();
+    let sam = service.join(&session.id, "Sam").await.unwrap();
+    assert_ne!(alex.id(), sam.id());
+
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.participants().len(), 2);
+    // Joining first doesn't make anyone the facilitator
+    assert!(fetched.facilitator().is_none());
+}
+
+pub async fn join_full_session_fails(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+    for i in 0..MAX_PARTICIPANTS {
+        service
+            .join(&session.id, &format!("Voter {i}"))
+            .await
+            .unwrap();
+    }
+
+    let result = service.join(&session.id, "Late").await;
+    assert!(matches!(result, Err(SessionServiceError::Validation(_))));
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.participants().len(), MAX_PARTICIPANTS);
+}
+
+pub async fn join_missing_session_fails(service: Arc<Service>) {
+    assert!(service.join(&SessionId::new(), "Alex").await.is_err());
+}
+
+pub async fn leave_removes_participants(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+    let alex = service.join(&session.id, "Alex").await.unwrap();
+
+    service.leave(&session.id, alex.id()).await.unwrap();
+    assert!(
+        service
+            .get(&session.id)
+            .await
+            .unwrap()
+            .participants()
+            .is_empty()
+    );
+
+    // Leaving twice, or as someone who never joined, is not an error
+    service.leave(&session.id, alex.id()).await.unwrap();
+    service
+        .leave(&session.id, &ParticipantId::new())
+        .await
+        .unwrap();
+}
+
+pub async fn leave_missing_session_fails(service: Arc<Service>) {
+    assert!(
+        service
+            .leave(&SessionId::new(), &ParticipantId::new())
+            .await
+            .is_err()
+    );
+}
+
+pub async fn modify_applies_changes(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+
+    let modified = service
+        .modify(
+            &session.id,
+            Box::new(|session| {
+                session.set_topic("Checkout".to_string());
+                Ok(())
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(modified.current_topic(), "Checkout");
+
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.current_topic(), "Checkout");
+}
+
+pub async fn failed_modify_changes_nothing(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+
+    let result = service
+        .modify(
+            &session.id,
+            Box::new(|session| {
+                session.set_topic("Half done".to_string());
+                Err(SessionServiceError::Rejected(Report::msg(
+                    "changed my mind",
+                )))
+            }),
+        )
+        .await;
+    assert!(matches!(result, Err(SessionServiceError::Rejected(_))));
+
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.current_topic(), "");
+}
+
+pub async fn modify_missing_session_fails(service: Arc<Service>) {
+    let result = service
+        .modify(&SessionId::new(), Box::new(|_| Ok(())))
+        .await;
+    assert!(matches!(result, Err(SessionServiceError::NotFound(_))));
+}
+
+pub async fn concurrent_modifications_are_not_lost(service: Arc<Service>) {
+    const VOTERS: usize = 50;
+
+    let session = service.create(create_request("Planning")).await.unwrap();
+    let mut voters = Vec::new();
+    for i in 0..VOTERS {
+        let participant = service
+            .join(&session.id, &format!("Voter {i}"))
+            .await
+            .unwrap();
+        voters.push(participant.id().clone());
+    }
+
+    // Everyone votes at once, each vote is a separate read-modify-write
+    let mut tasks = tokio::task::JoinSet::new();
+    for participant_id in voters {
+        let service = service.clone();
+        let session_id = session.id.clone();
+        tasks.spawn(async move {
+            service
+                .modify(
+                    &session_id,
+                    Box::new(move |session| {
+                        session.point(&participant_id, Some("5".to_string()));
+                        Ok(())
+                    }),
+                )
+                .await
+                .unwrap();
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        result.unwrap();
+    }
+
+    let fetched = service.get(&session.id).await.unwrap();
+    assert_eq!(fetched.current_points().len(), VOTERS);
+    assert!(
+        fetched
+            .current_points()
+            .values()
+            .all(|points| points.as_deref() == Some("5"))
+    );
+}
+
+pub async fn end_removes_session(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+
+    service.end(&session.id).await.unwrap();
+
+    assert!(service.get(&session.id).await.is_err());
+    assert!(service.list().await.unwrap().is_empty());
+    assert!(service.end(&session.id).await.is_err());
+}
+
+pub async fn end_if_keeps_sessions_failing_the_check(service: Arc<Service>) {
+    let session = service.create(create_request("Planning")).await.unwrap();
+
+    let result = service
+        .end_if(
+            &session.id,
+            Box::new(|_| Err(SessionServiceError::Rejected(Report::msg("not yet")))),
+        )
+        .await;
+    assert!(matches!(result, Err(SessionServiceError::Rejected(_))));
+    assert!(service.get(&session.id).await.is_ok());
+
+    service
+        .end_if(&session.id, Box::new(|_| Ok(())))
+        .await
+        .unwrap();
+    assert!(service.get(&session.id).await.is_err());
+    assert!(
+        service
+            .end_if(&session.id, Box::new(|_| Ok(())))
+            .await
+            .is_err()
+    );
+}
+
+macro_rules! conformance_tests {
+    ($($backend:ident => $service:expr),* $(,)?) => {
+        $(
+            mod $backend {
+                conformance_tests!(@tests $service;
+                    create_then_get,
+                    get_missing_session_fails,
+                    create_rejects_invalid_requests,
+                    list_returns_all_sessions,
+                    update_persists_changes,
+                    update_missing_session_fails,
+                    join_adds_participants,
+                    join_full_session_fails,
+                    join_missing_session_fails,
+                    leave_removes_participants,
+                    leave_missing_session_fails,
+                    modify_applies_changes,
+                    failed_modify_changes_nothing,
+                    modify_missing_session_fails,
+                    concurrent_modifications_are_not_lost,
+                    end_removes_session,
+                    end_if_keeps_sessions_failing_the_check,
+                );
+            }
+        )*
+    };
+    (@tests $service:expr; $($test:ident),* $(,)?) => {
+        $(
+            #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+            async fn $test() {
+                let service: std::sync::Arc<super::Service> = std::sync::Arc::new($service);
+                super::$test(service).await;
+            }
+        )*
+    };
+}
+
+conformance_tests! {
+    in_memory => super::super::SessionServiceInMemory::new(),
+    sqlite => super::super::SessionServiceSqlite::open_in_memory().unwrap(),
+}
@@ -0,0 +1,39 @@
+use kiko::{data::ValidationError, errors::Report, id::SessionId};
+
+/// Errors returned by every [`SessionService`](super::SessionService) implementation.
+///
+/// Backends sort their failures into these kinds, so callers can tell a missing
+/// session from a broken database whichever backend is in use.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionServiceError {
+    /// No session exists with this ID, it may have ended.
+    #[error("Session {0} not found")]
+    NotFound(SessionId),
+    /// The change clashes with the stored state, e.g. a session with the same ID exists.
+    #[error("{0}")]
+    Conflict(String),
+    /// The request broke the validation rules, e.g. a session without a name.
+    #[error("{}", join_violations(.0))]
+    Validation(Vec<ValidationError>),
+    /// The mutation passed to [`modify`](super::SessionService::modify) refused the change.
+    #[error("{0}")]
+    Rejected(Report),
+    /// The backend failed to read or write the session.
+    #[error("Storage failed: {0}")]
+    Storage(Report),
+}
+
+impl SessionServiceError {
+    /// Wraps any error a backend's storage returned.
+    pub fn storage(error: impl Into<Report>) -> Self {
+        Self::Storage(error.into())
+    }
+}
+
+fn join_violations(violations: &[ValidationError]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,9 +1,21 @@
 //! Backend services for session and participant management.
 //!
 //! This module provides the service layer abstractions and implementations
-//! for managing sessions and their participants. Currently includes an
-//! in-memory implementation suitable for development and testing.
+//! for managing sessions and their participants. Sessions can be kept in memory,
+//! which is suitable for development and testing, or persisted to SQLite.
 
+pub mod error;
+pub mod resume;
 pub mod sessions;
+pub mod sqlite;
 
+#[cfg(test)]
+mod conformance;
+
+pub use error::*;
+pub use resume::*;
 pub use sessions::*;
+pub use sqlite::*;
+
+/// A type-erased session service, so the storage backend can be chosen at startup.
+pub type DynSessionService = dyn SessionService<Error = SessionServiceError> + Send + Sync;
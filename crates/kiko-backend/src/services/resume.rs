@@ -0,0 +1,157 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use dashmap::DashMap;
+
+use kiko::id::{ParticipantId, ResumeToken, SessionId};
+
+/// How long a disconnected participant is kept around before being removed.
+pub const RESUME_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Marks which connection currently acts as a participant.
+///
+/// Every join or resume takes a new claim. A connection that drops only removes
+/// its participant if nobody has claimed it since, so a stale socket that is
+/// noticed late can't kick out the participant's new connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Claim(u64);
+
+/// Keeps track of the resume tokens handed out to participants.
+///
+/// A resume token proves that a connection may act as a participant it didn't
+/// create itself, e.g. after reconnecting. Tokens live in memory only, so they
+/// don't survive a backend restart even when sessions do.
+pub struct ResumeTokens {
+    grace_period: Duration,
+    tokens: DashMap<ResumeToken, (SessionId, ParticipantId)>,
+    claims: DashMap<ParticipantId, Claim>,
+    next_claim: AtomicU64,
+}
+
+impl ResumeTokens {
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            tokens: DashMap::new(),
+            claims: DashMap::new(),
+            next_claim: AtomicU64::new(0),
+        }
+    }
+
+    /// How long a disconnected participant can still be resumed.
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Issues a new token for a participant of a session.
+    pub fn issue(&self, session_id: &SessionId, participant_id: &ParticipantId) -> ResumeToken {
+        let token = ResumeToken::new();
+        self.tokens
+            .insert(token.clone(), (session_id.clone(), participant_id.clone()));
+        token
+    }
+
+    /// Returns the session and participant a token belongs to.
+    pub fn lookup(&self, token: &ResumeToken) -> Option<(SessionId, ParticipantId)> {
+        self.tokens.get(token).map(|entry| entry.value().clone())
+    }
+
+    /// Makes the calling connection the one acting as `participant_id`.
+    pub fn claim(&self, participant_id: &ParticipantId) -> Claim {
+        let claim = Claim(self.next_claim.fetch_add(1, Ordering::Relaxed));
+        self.claims.insert(participant_id.clone(), claim);
+        claim
+    }
+
+    /// Returns true if `claim` is still the latest claim on the participant.
+    pub fn is_current(&self, participant_id: &ParticipantId, claim: Claim) -> bool {
+        self.claims
+            .get(participant_id)
+            .is_some_and(|current| *current == claim)
+    }
+
+    /// Forgets the participant if `claim` is still the latest claim on it.
+    ///
+    /// Returns true if the participant was forgotten and should be removed.
+    pub fn expire(&self, participant_id: &ParticipantId, claim: Claim) -> bool {
+        let expired = self
+            .claims
+            .remove_if(participant_id, |_, current| *current == claim)
+            .is_some();
+        if expired {
+            self.revoke_participant(participant_id);
+        }
+        expired
+    }
+
+    /// Invalidates every token and claim of a participant.
+    pub fn revoke_participant(&self, participant_id: &ParticipantId) {
+        self.claims.remove(participant_id);
+        self.tokens.retain(|_, (_, id)| id != participant_id);
+    }
+
+    /// Invalidates every token and claim for a session.
+    pub fn revoke_session(&self, session_id: &SessionId) {
+        self.tokens.retain(|_, (id, participant_id)| {
+            if id == session_id {
+                self.claims.remove(participant_id);
+            }
+            id != session_id
+        });
+    }
+}
+
+impl Default for ResumeTokens {
+    fn default() -> Self {
+        Self::new(RESUME_GRACE_PERIOD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_resolve_until_revoked() {
+        let tokens = ResumeTokens::default();
+        let session_id = SessionId::new();
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+
+        let alex_token = tokens.issue(&session_id, &alex);
+        let sam_token = tokens.issue(&session_id, &sam);
+        assert_ne!(alex_token, sam_token);
+        assert_eq!(
+            tokens.lookup(&alex_token),
+            Some((session_id.clone(), alex.clone()))
+        );
+
+        tokens.revoke_participant(&alex);
+        assert_eq!(tokens.lookup(&alex_token), None);
+        assert!(tokens.lookup(&sam_token).is_some());
+
+        tokens.revoke_session(&session_id);
+        assert_eq!(tokens.lookup(&sam_token), None);
+    }
+
+    #[test]
+    fn only_the_latest_claim_expires_a_participant() {
+        let tokens = ResumeTokens::default();
+        let session_id = SessionId::new();
+        let alex = ParticipantId::new();
+        let token = tokens.issue(&session_id, &alex);
+
+        let dropped = tokens.claim(&alex);
+        let resumed = tokens.claim(&alex);
+        assert!(!tokens.is_current(&alex, dropped));
+
+        // The stale connection can't expire the resumed participant
+        assert!(!tokens.expire(&alex, dropped));
+        assert!(tokens.lookup(&token).is_some());
+
+        assert!(tokens.expire(&alex, resumed));
+        assert_eq!(tokens.lookup(&token), None);
+    }
+}
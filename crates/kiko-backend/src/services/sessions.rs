@@ -1,7 +1,37 @@
 use async_trait::async_trait;
 use dashmap::DashMap;
 
-use kiko::id::{ParticipantId, SessionId};
+use dashmap::mapref::entry::Entry;
+
+use kiko::{
+    data::{MAX_PARTICIPANTS, ValidationError, validate_participant_name},
+    id::{ParticipantId, SessionId},
+};
+
+use crate::services::SessionServiceError;
+
+/// A change to apply to a session with [`SessionService::modify`].
+///
+/// Returning an error aborts the change and leaves the stored session untouched.
+pub type SessionMutation<Session, Error> =
+    Box<dyn FnOnce(&mut Session) -> Result<(), Error> + Send>;
+
+/// A check a session has to pass before [`SessionService::end_if`] removes it.
+///
+/// Returning an error keeps the session.
+pub type SessionCheck<Session, Error> = Box<dyn FnOnce(&Session) -> Result<(), Error> + Send>;
+
+/// Refuses new participants once a session is full, shared by every backend.
+pub fn check_not_full(session: &kiko::data::Session) -> Result<(), SessionServiceError> {
+    if session.is_full() {
+        return Err(SessionServiceError::Validation(vec![
+            ValidationError::SessionFull {
+                max: MAX_PARTICIPANTS,
+            },
+        ]));
+    }
+    Ok(())
+}
 
 /// A trait for managing sessions and their participants.
 ///
@@ -21,6 +51,7 @@ use kiko::id::{ParticipantId, SessionId};
 ///     let create_request = CreateSession {
 ///         name: "Team Meeting".to_string(),
 ///         duration: std::time::Duration::from_secs(3600),
+///         deck: kiko::data::Deck::default(),
 ///     };
 ///     let session = service.create(create_request).await?;
 ///     
@@ -40,12 +71,18 @@ pub trait SessionService<Session = kiko::data::Session, Participant = kiko::data
 
     /// Creates a new session with the given name and duration.
     ///
+    /// # Errors
+    ///
+    /// Returns [`SessionServiceError::Validation`] if the request breaks the
+    /// [validation rules](kiko::data::CreateSession::validate).
+    ///
     /// # Examples
     ///
     /// ```rust
     /// let create_request = CreateSession {
     ///     name: "Daily Standup".to_string(),
     ///     duration: Duration::from_secs(1800), // 30 minutes
+    ///     deck: Deck::TShirt,
     /// };
     /// let session = service.create(create_request).await?;
     /// ```
@@ -62,6 +99,34 @@ pub trait SessionService<Session = kiko::data::Session, Participant = kiko::data
         session: &Session,
     ) -> Result<Session, Self::Error>;
 
+    /// Atomically applies `mutation` to a session and stores the result.
+    ///
+    /// Unlike a [`get`](Self::get) followed by an [`update`](Self::update), no other
+    /// change to the same session can land between reading and writing it, so
+    /// concurrent modifications are never lost. Every stored change bumps the
+    /// session's [`version`](kiko::data::Session::version). Returns the updated session.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let session = service
+    ///     .modify(&session_id, Box::new(|session| {
+    ///         session.set_topic("Checkout flow".to_string());
+    ///         Ok(())
+    ///     }))
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session doesn't exist, or the error returned by
+    /// `mutation`, in which case nothing is stored.
+    async fn modify(
+        &self,
+        session_id: &SessionId,
+        mutation: SessionMutation<Session, Self::Error>,
+    ) -> Result<Session, Self::Error>;
+
     /// Retrieves a session by its unique identifier.
     ///
     /// # Examples
@@ -96,7 +161,8 @@ pub trait SessionService<Session = kiko::data::Session, Participant = kiko::data
     ///
     /// # Errors
     ///
-    /// Returns an error if the session doesn't exist.
+    /// Returns an error if the session doesn't exist, or [`SessionServiceError::Validation`]
+    /// if the name is invalid or the session is full.
     async fn join(
         &self,
         session_id: &SessionId,
@@ -133,6 +199,20 @@ pub trait SessionService<Session = kiko::data::Session, Participant = kiko::data
     ///
     /// Returns an error if the session doesn't exist.
     async fn end(&self, session_id: &SessionId) -> Result<(), Self::Error>;
+
+    /// Ends a session like [`SessionService::end`], but only if it passes `check`.
+    ///
+    /// The check and the removal are atomic, so the session can't change in between.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session doesn't exist, or the error returned by
+    /// `check`, in which case the session is kept.
+    async fn end_if(
+        &self,
+        session_id: &SessionId,
+        check: SessionCheck<Session, Self::Error>,
+    ) -> Result<(), Self::Error>;
 }
 
 /// An in-memory implementation of the `SessionService` trait.
@@ -151,6 +231,7 @@ pub trait SessionService<Session = kiko::data::Session, Participant = kiko::data
 /// let create_request = CreateSession {
 ///     name: "Team Planning".to_string(),
 ///    duration: std::time::Duration::from_secs(3600), // 1 hour
+///    deck: Deck::Fibonacci,
 /// };
 /// let session = service.create(create_request).await.unwrap();
 /// // Join a participant
@@ -180,16 +261,27 @@ impl Default for SessionServiceInMemory {
 
 #[async_trait]
 impl SessionService for SessionServiceInMemory {
-    type Error = kiko::errors::Report;
+    type Error = SessionServiceError;
 
     async fn create(
         &self,
         session: kiko::data::CreateSession,
     ) -> Result<kiko::data::Session, Self::Error> {
-        let new_session = kiko::data::Session::new(session.name, session.duration);
-        self.sessions
-            .insert(new_session.id.clone(), new_session.clone());
-        Ok(new_session)
+        let session = session
+            .validate()
+            .map_err(SessionServiceError::Validation)?;
+
+        let new_session = kiko::data::Session::new(session.name, session.duration, session.deck);
+        match self.sessions.entry(new_session.id.clone()) {
+            Entry::Occupied(_) => Err(SessionServiceError::Conflict(format!(
+                "Session {} already exists",
+                new_session.id
+            ))),
+            Entry::Vacant(entry) => {
+                entry.insert(new_session.clone());
+                Ok(new_session)
+            }
+        }
     }
 
     async fn update(
@@ -202,15 +294,35 @@ impl SessionService for SessionServiceInMemory {
             self.sessions.insert(session_id.clone(), session.clone());
             Ok(session.clone())
         } else {
-            Err(Self::Error::msg("Session not found"))
+            Err(SessionServiceError::NotFound(session_id.clone()))
         }
     }
 
+    async fn modify(
+        &self,
+        session_id: &SessionId,
+        mutation: SessionMutation<kiko::data::Session, Self::Error>,
+    ) -> Result<kiko::data::Session, Self::Error> {
+        // Holding the entry locks its shard until the change is stored
+        let mut session_entry = self
+            .sessions
+            .get_mut(session_id)
+            .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+
+        // Mutate a copy so a failed mutation leaves the stored session untouched
+        let mut session = session_entry.clone();
+        mutation(&mut session)?;
+        session.next_version();
+        *session_entry = session.clone();
+
+        Ok(session)
+    }
+
     async fn get(&self, session_id: &SessionId) -> Result<kiko::data::Session, Self::Error> {
         self.sessions
             .get(session_id)
             .map(|entry| entry.value().clone())
-            .ok_or_else(|| Self::Error::msg("Session not found"))
+            .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))
     }
 
     async fn list(&self) -> Result<Vec<kiko::data::Session>, Self::Error> {
@@ -226,14 +338,16 @@ impl SessionService for SessionServiceInMemory {
         session_id: &SessionId,
         participant_name: &str,
     ) -> Result<kiko::data::Participant, Self::Error> {
+        let participant_name = validate_participant_name(participant_name)
+            .map_err(|error| SessionServiceError::Validation(vec![error]))?;
         let mut session_entry = self
             .sessions
             .get_mut(session_id)
-            .ok_or_else(|| Self::Error::msg("Session not found"))?;
+            .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+        check_not_full(&session_entry)?;
 
         let participant_id = ParticipantId::new();
-        let new_participant =
-            kiko::data::Participant::new(participant_id, participant_name.to_string());
+        let new_participant = kiko::data::Participant::new(participant_id, participant_name);
 
         session_entry.add_participant(new_participant.clone());
         Ok(new_participant)
@@ -247,7 +361,7 @@ impl SessionService for SessionServiceInMemory {
         let mut session_entry = self
             .sessions
             .get_mut(session_id)
-            .ok_or_else(|| Self::Error::msg("Session not found"))?;
+            .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
 
         session_entry.remove_participant(participant_id);
         Ok(())
@@ -257,6 +371,22 @@ impl SessionService for SessionServiceInMemory {
         self.sessions
             .remove(session_id)
             .map(|_| ())
-            .ok_or_else(|| Self::Error::msg("Session not found"))
+            .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))
+    }
+
+    async fn end_if(
+        &self,
+        session_id: &SessionId,
+        check: SessionCheck<kiko::data::Session, Self::Error>,
+    ) -> Result<(), Self::Error> {
+        // Holding the entry locks its shard until the session is removed
+        match self.sessions.entry(session_id.clone()) {
+            Entry::Occupied(entry) => {
+                check(entry.get())?;
+                entry.remove();
+                Ok(())
+            }
+            Entry::Vacant(_) => Err(SessionServiceError::NotFound(session_id.clone())),
+        }
     }
 }
@@ -0,0 +1,389 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+
+use kiko::{
+    data::{CreateSession, Participant, Session, validate_participant_name},
+    errors::Report,
+    id::{ParticipantId, SessionId},
+    serde_json,
+};
+
+use crate::services::{
+    SessionCheck, SessionMutation, SessionService, SessionServiceError, check_not_full,
+};
+
+/// Schema migrations, applied in order.
+///
+/// The schema version is tracked with SQLite's `user_version` pragma, so a
+/// migration must never be edited once released; add a new one instead.
+const MIGRATIONS: &[&str] = &[include_str!("../../migrations/0001_create_sessions.sql")];
+
+/// A SQLite-backed implementation of the `SessionService` trait.
+///
+/// Sessions survive backend restarts. Each session is stored as a JSON document,
+/// and every operation runs on a blocking thread against a single shared connection,
+/// so read-modify-write operations like `join` are never interleaved.
+///
+/// # Examples
+/// ```rust
+/// let service = SessionServiceSqlite::open("kiko.db")?;
+/// let session = service.create(CreateSession {
+///     name: "Team Planning".to_string(),
+///     duration: std::time::Duration::from_secs(3600),
+///     deck: Deck::Fibonacci,
+/// }).await?;
+/// ```
+pub struct SessionServiceSqlite {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SessionServiceSqlite {
+    /// Opens (or creates) the database at `path` and brings its schema up to date.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Report> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::from_connection(conn)
+    }
+
+    /// Opens a private in-memory database, mostly useful for tests.
+    pub fn open_in_memory() -> Result<Self, Report> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, Report> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs `f` with exclusive access to the connection on a blocking thread.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T, SessionServiceError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, SessionServiceError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().map_err(|_| {
+                SessionServiceError::storage(Report::msg("SQLite connection lock poisoned"))
+            })?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(SessionServiceError::storage)?
+    }
+}
+
+impl From<rusqlite::Error> for SessionServiceError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::storage(error)
+    }
+}
+
+/// Sessions are stored as JSON, so a document that doesn't parse is a storage failure.
+impl From<serde_json::Error> for SessionServiceError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::storage(error)
+    }
+}
+
+/// Applies any migrations the database hasn't seen yet.
+fn migrate(conn: &mut Connection) -> Result<(), Report> {
+    let version: usize =
+        conn.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))? as usize;
+
+    if version > MIGRATIONS.len() {
+        return Err(Report::msg(format!(
+            "Database schema version {version} is newer than this build supports ({})",
+            MIGRATIONS.len()
+        )));
+    }
+
+    let tx = conn.transaction()?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn load(conn: &Connection, session_id: &SessionId) -> Result<Option<Session>, SessionServiceError> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM sessions WHERE id = ?1",
+            params![session_id.as_ref()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+}
+
+/// Overwrites an existing session, returning false if it doesn't exist.
+fn store(
+    conn: &Connection,
+    session_id: &SessionId,
+    session: &Session,
+) -> Result<bool, SessionServiceError> {
+    let changed = conn.execute(
+        "UPDATE sessions SET data = ?2 WHERE id = ?1",
+        params![session_id.as_ref(), serde_json::to_string(session)?],
+    )?;
+    Ok(changed > 0)
+}
+
+#[async_trait]
+impl SessionService for SessionServiceSqlite {
+    type Error = SessionServiceError;
+
+    async fn create(&self, session: CreateSession) -> Result<Session, Self::Error> {
+        let session = session
+            .validate()
+            .map_err(SessionServiceError::Validation)?;
+
+        let new_session = Session::new(session.name, session.duration, session.deck);
+        let data = serde_json::to_string(&new_session)?;
+        let id = new_session.id.to_string();
+        let started = new_session.started() as i64;
+
+        self.with_connection(move |conn| {
+            let result = conn.execute(
+                "INSERT INTO sessions (id, started, data) VALUES (?1, ?2, ?3)",
+                params![id, started, data],
+            );
+            match result {
+                Ok(_) => Ok(()),
+                Err(error)
+                    if error.sqlite_error_code()
+                        == Some(rusqlite::ErrorCode::ConstraintViolation) =>
+                {
+                    Err(SessionServiceError::Conflict(format!(
+                        "Session {id} already exists"
+                    )))
+                }
+                Err(error) => Err(error.into()),
+            }
+        })
+        .await?;
+
+        Ok(new_session)
+    }
+
+    async fn update(
+        &self,
+        session_id: &SessionId,
+        session: &Session,
+    ) -> Result<Session, Self::Error> {
+        let session_id = session_id.clone();
+        let session = session.clone();
+
+        self.with_connection(move |conn| {
+            if store(conn, &session_id, &session)? {
+                Ok(session)
+            } else {
+                Err(SessionServiceError::NotFound(session_id))
+            }
+        })
+        .await
+    }
+
+    async fn modify(
+        &self,
+        session_id: &SessionId,
+        mutation: SessionMutation<Session, Self::Error>,
+    ) -> Result<Session, Self::Error> {
+        let session_id = session_id.clone();
+
+        self.with_connection(move |conn| {
+            // Take the write lock up front so other processes can't sneak in a write
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+            let mut session = load(&tx, &session_id)?
+                .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+            mutation(&mut session)?;
+            session.next_version();
+            store(&tx, &session_id, &session)?;
+
+            tx.commit()?;
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn get(&self, session_id: &SessionId) -> Result<Session, Self::Error> {
+        let session_id = session_id.clone();
+        self.with_connection(move |conn| {
+            load(conn, &session_id)?
+                .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))
+        })
+        .await
+    }
+
+    async fn list(&self) -> Result<Vec<Session>, Self::Error> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare("SELECT data FROM sessions ORDER BY started")?;
+            let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+            let mut sessions = Vec::new();
+            for data in rows {
+                sessions.push(serde_json::from_str(&data?)?);
+            }
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn join(
+        &self,
+        session_id: &SessionId,
+        participant_name: &str,
+    ) -> Result<Participant, Self::Error> {
+        let session_id = session_id.clone();
+        let participant_name = validate_participant_name(participant_name)
+            .map_err(|error| SessionServiceError::Validation(vec![error]))?;
+
+        self.with_connection(move |conn| {
+            let mut session = load(conn, &session_id)?
+                .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+            check_not_full(&session)?;
+
+            let participant = Participant::new(ParticipantId::new(), participant_name);
+            session.add_participant(participant.clone());
+            store(conn, &session_id, &session)?;
+
+            Ok(participant)
+        })
+        .await
+    }
+
+    async fn leave(
+        &self,
+        session_id: &SessionId,
+        participant_id: &ParticipantId,
+    ) -> Result<(), Self::Error> {
+        let session_id = session_id.clone();
+        let participant_id = participant_id.clone();
+
+        self.with_connection(move |conn| {
+            let mut session = load(conn, &session_id)?
+                .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+
+            session.remove_participant(&participant_id);
+            store(conn, &session_id, &session)?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn end(&self, session_id: &SessionId) -> Result<(), Self::Error> {
+        let session_id = session_id.clone();
+        self.with_connection(move |conn| {
+            let removed = conn.execute(
+                "DELETE FROM sessions WHERE id = ?1",
+                params![session_id.as_ref()],
+            )?;
+            if removed > 0 {
+                Ok(())
+            } else {
+                Err(SessionServiceError::NotFound(session_id))
+            }
+        })
+        .await
+    }
+
+    async fn end_if(
+        &self,
+        session_id: &SessionId,
+        check: SessionCheck<Session, Self::Error>,
+    ) -> Result<(), Self::Error> {
+        let session_id = session_id.clone();
+
+        self.with_connection(move |conn| {
+            // Take the write lock up front so the session can't change before it is deleted
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+            let session = load(&tx, &session_id)?
+                .ok_or_else(|| SessionServiceError::NotFound(session_id.clone()))?;
+            check(&session)?;
+            tx.execute(
+                "DELETE FROM sessions WHERE id = ?1",
+                params![session_id.as_ref()],
+            )?;
+
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kiko::data::Deck;
+    use std::time::Duration;
+
+    fn temp_db_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("kiko-{name}-{}.db", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn sessions_survive_reopening() {
+        let path = temp_db_path("reopen");
+        let _ = std::fs::remove_file(&path);
+
+        let session_id = {
+            let service = SessionServiceSqlite::open(&path).unwrap();
+            let session = service
+                .create(CreateSession {
+                    name: "Durable".to_string(),
+                    duration: Duration::from_secs(3600),
+                    deck: Deck::TShirt,
+                })
+                .await
+                .unwrap();
+            service.join(&session.id, "Alex").await.unwrap();
+            session.id
+        };
+
+        let service = SessionServiceSqlite::open(&path).unwrap();
+        let session = service.get(&session_id).await.unwrap();
+        assert_eq!(session.name(), "Durable");
+        assert_eq!(session.deck(), &Deck::TShirt);
+        assert_eq!(session.participants().len(), 1);
+
+        drop(service);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+
+        let version: i64 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", (MIGRATIONS.len() + 1) as i64)
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
@@ -1,15 +1,41 @@
+use gloo_timers::callback::Interval;
 use yew::prelude::*;
 
-use crate::hooks::ConnectionState;
+use crate::hooks::{ConnectionState, Reconnect};
 
 #[derive(Properties, PartialEq)]
 pub struct ConnectionIndicatorProps {
     pub state: ConnectionState,
+    pub reconnect: Option<Reconnect>,
 }
 
 #[function_component(ConnectionIndicator)]
 pub fn connection_indicator(props: &ConnectionIndicatorProps) -> Html {
     let state = &props.state;
+    let reconnect = props.reconnect.as_ref();
+
+    // Re-render every second while waiting, so the countdown stays current
+    let refresh = use_force_update();
+    use_effect_with(reconnect.is_some(), move |waiting| {
+        let interval = waiting.then(|| Interval::new(1000, move || refresh.force_update()));
+        move || drop(interval)
+    });
+
+    let label = match (state, reconnect) {
+        (ConnectionState::Connected, _) => "Connected".to_string(),
+        (ConnectionState::Connecting, Some(reconnect)) => {
+            format!("Reconnecting... (attempt {})", reconnect.attempt)
+        }
+        (ConnectionState::Connecting, None) => "Connecting...".to_string(),
+        (_, Some(reconnect)) => {
+            let seconds = ((reconnect.next_retry_at - js_sys::Date::now()) / 1000.0)
+                .ceil()
+                .max(0.0);
+            format!("Reconnecting in {seconds}s (attempt {})", reconnect.attempt)
+        }
+        (ConnectionState::Disconnected, None) => "Disconnected".to_string(),
+        (ConnectionState::Error(_), None) => "Connection Error".to_string(),
+    };
 
     html! {
         <div class="flex items-center space-x-2">
@@ -27,14 +53,7 @@ pub fn connection_indicator(props: &ConnectionIndicatorProps) -> Html {
                     ConnectionState::Disconnected => "text-gray-600 dark:text-gray-400",
                     ConnectionState::Error(_) => "text-red-600 dark:text-red-400",
                 }
-            )}>{
-                match &state {
-                    ConnectionState::Connected => "Connected",
-                    ConnectionState::Connecting => "Connecting...",
-                    ConnectionState::Disconnected => "Disconnected",
-                    ConnectionState::Error(_) => "Connection Error",
-                }
-            }</span>
+            )}>{ label }</span>
         </div>
     }
 }
@@ -1,14 +1,22 @@
 use std::time::Duration;
 
+use gloo_storage::{LocalStorage, Storage};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 use kiko::{
     async_callback,
-    data::{self, Session},
+    data::{self, Deck, Session},
 };
 
-use crate::providers::api;
+use crate::providers::{api, use_backend};
+
+/// Local storage key for the token that makes this browser a session's facilitator.
+///
+/// Kept in local storage so the tab opened for the new session can read it.
+pub fn facilitator_key(session_id: &str) -> String {
+    format!("kiko.facilitator.{session_id}")
+}
 
 #[derive(Properties, PartialEq)]
 pub struct CreateSessionProps {
@@ -25,12 +33,15 @@ pub struct CreateSessionProps {
 
 #[function_component(CreateSession)]
 pub fn create_session(props: &CreateSessionProps) -> Html {
-    let api = use_memo((), |_| api::create());
+    let backend = use_backend();
+    let api = use_memo(backend, api::create);
 
     // Form state
     let session_name = use_state(String::new);
     let duration_hours = use_state(|| 0u32);
     let duration_minutes = use_state(|| 30u32); // Default to 30 minutes
+    let deck = use_state(Deck::default);
+    let custom_cards = use_state(String::new);
 
     // UI state
     let loading = use_state(|| false);
@@ -38,7 +49,7 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
     let success = use_state(|| false);
 
     // Form validation - fix the logic to work with defaults
-    let is_valid = !session_name.is_empty()
+    let is_valid = !session_name.trim().is_empty()
         && (*duration_hours > 0 || *duration_minutes > 0)
         && (*duration_hours <= 24);
 
@@ -59,11 +70,11 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
         let duration_hours = duration_hours.clone();
         let error_msg = error_msg.clone();
         Callback::from(move |e: Event| {
-            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
-                if let Ok(hours) = input.value().parse::<u32>() {
-                    duration_hours.set(hours);
-                    error_msg.set(None);
-                }
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>()
+                && let Ok(hours) = input.value().parse::<u32>()
+            {
+                duration_hours.set(hours);
+                error_msg.set(None);
             }
         })
     };
@@ -72,17 +83,36 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
         let duration_minutes = duration_minutes.clone();
         let error_msg = error_msg.clone();
         Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>()
+                && let Ok(minutes) = input.value().parse::<u32>()
+                && minutes < 60
+            {
+                duration_minutes.set(minutes);
+                error_msg.set(None);
+            }
+        })
+    };
+
+    let on_custom_cards_change = {
+        let custom_cards = custom_cards.clone();
+        let error_msg = error_msg.clone();
+        Callback::from(move |e: InputEvent| {
             if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
-                if let Ok(minutes) = input.value().parse::<u32>() {
-                    if minutes < 60 {
-                        duration_minutes.set(minutes);
-                        error_msg.set(None);
-                    }
-                }
+                custom_cards.set(input.value());
+                error_msg.set(None);
             }
         })
     };
 
+    let set_deck = {
+        let deck = deck.clone();
+        let error_msg = error_msg.clone();
+        Callback::from(move |selected: Deck| {
+            deck.set(selected);
+            error_msg.set(None);
+        })
+    };
+
     // Submit handler - using manual approach that works
     let on_session_created = props.on_session_created.clone();
     let on_submit = async_callback!([
@@ -90,6 +120,8 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
         session_name,
         duration_hours,
         duration_minutes,
+        deck,
+        custom_cards,
         loading,
         error_msg,
         success,
@@ -97,22 +129,34 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
     ] |e: SubmitEvent| {
         e.prevent_default();
 
-        // Always prevent submission if form is invalid
-        if session_name.is_empty() {
-            error_msg.set(Some("Session name is required".to_string()));
-            return;
-        }
-
         let total_seconds = (*duration_hours * 3600) + (*duration_minutes * 60);
-        if total_seconds == 0 {
-            error_msg.set(Some("Duration must be greater than 0".to_string()));
-            return;
-        }
 
-        if *duration_hours > 24 {
-            error_msg.set(Some("Duration cannot exceed 24 hours".to_string()));
-            return;
-        }
+        // Custom decks are entered as a comma separated list of cards
+        let selected_deck = match &*deck {
+            Deck::Custom(_) => Deck::Custom(
+                custom_cards
+                    .split(',')
+                    .map(|card| card.trim().to_string())
+                    .filter(|card| !card.is_empty())
+                    .collect(),
+            ),
+            preset => preset.clone(),
+        };
+
+        // Check the same rules the backend enforces before sending anything
+        let create_request = data::CreateSession {
+            name: (*session_name).clone(),
+            duration: Duration::from_secs(total_seconds as u64),
+            deck: selected_deck,
+        };
+        let create_request = match create_request.validate() {
+            Ok(create_request) => create_request,
+            Err(errors) => {
+                let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                error_msg.set(Some(messages.join(". ")));
+                return;
+            }
+        };
 
         // Don't submit if already loading or successfully created
         if *loading || *success {
@@ -122,13 +166,14 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
         loading.set(true);
         error_msg.set(None);
 
-        let create_request = data::CreateSession {
-            name: (*session_name).clone(),
-            duration: Duration::from_secs(total_seconds as u64),
-        };
-
         match api.create_session(&create_request).await {
-            Ok(session) => {
+            Ok(created) => {
+                let session = created.session;
+                let key = facilitator_key(session.id.as_str());
+                if let Err(err) = LocalStorage::set(key, created.facilitator_token) {
+                    error_msg.set(Some(format!("Failed to store the facilitator token: {err}")));
+                }
+
                 loading.set(false);
                 success.set(true);
 
@@ -166,6 +211,8 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
                     session_name.set(String::new());
                     duration_hours.set(0);
                     duration_minutes.set(30);
+                    deck.set(Deck::default());
+                    custom_cards.set(String::new());
                     success.set(false);
                 });
             }
@@ -236,6 +283,7 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
                             type="text"
                             class="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
                             placeholder="Sprint Planning, Story Estimation..."
+                            maxlength={data::MAX_SESSION_NAME_CHARS.to_string()}
                             value={(*session_name).clone()}
                             oninput={on_name_change}
                             disabled={*loading}
@@ -324,6 +372,62 @@ pub fn create_session(props: &CreateSessionProps) -> Html {
                         </div>
                     </div>
 
+                    // Deck Section
+                    <div>
+                        <label class="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                            { "Estimation Deck" }
+                        </label>
+                        <div class="flex flex-wrap gap-1 mb-2">
+                            {
+                                Deck::PRESETS.iter().cloned().chain(std::iter::once(Deck::Custom(Vec::new()))).map(|option| {
+                                    let is_selected = std::mem::discriminant(&option) == std::mem::discriminant(&*deck);
+                                    let title = option.cards().join(", ");
+                                    html! {
+                                        <button
+                                            type="button"
+                                            class={classes!(
+                                                "px-2", "py-1", "text-xs", "rounded", "border", "disabled:opacity-50",
+                                                if is_selected {
+                                                    "bg-blue-600 text-white border-blue-600"
+                                                } else {
+                                                    "bg-gray-100 dark:bg-gray-700 hover:bg-gray-200 dark:hover:bg-gray-600 border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300"
+                                                }
+                                            )}
+                                            title={title}
+                                            onclick={set_deck.reform({
+                                                let option = option.clone();
+                                                move |_| option.clone()
+                                            })}
+                                            disabled={*loading}
+                                        >
+                                            { option.name() }
+                                        </button>
+                                    }
+                                }).collect::<Html>()
+                            }
+                        </div>
+                        {
+                            if matches!(*deck, Deck::Custom(_)) {
+                                html! {
+                                    <input
+                                        id="custom-deck"
+                                        type="text"
+                                        class="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                                        placeholder="1, 2, 4, ?, ☕, ∞"
+                                        value={(*custom_cards).clone()}
+                                        oninput={on_custom_cards_change}
+                                        disabled={*loading}
+                                        aria-label="Custom Deck Cards"
+                                    />
+                                }
+                            } else {
+                                html! {
+                                    <p class="text-xs text-gray-500 dark:text-gray-400">{ deck.cards().join("  ") }</p>
+                                }
+                            }
+                        }
+                    </div>
+
                     // Error Message
                     {
                         if let Some(error) = error_msg.as_ref() {
@@ -2,8 +2,14 @@
 
 pub mod connection_indicator;
 pub mod create_session;
+pub mod request_status;
+pub mod round_history;
 pub mod session_view;
+pub mod story_queue;
 
 pub use connection_indicator::*;
 pub use create_session::*;
+pub use request_status::*;
+pub use round_history::*;
 pub use session_view::*;
+pub use story_queue::*;
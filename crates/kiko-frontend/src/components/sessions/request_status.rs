@@ -0,0 +1,40 @@
+use yew::prelude::*;
+
+use crate::hooks::{RequestStatus, Requests};
+
+#[derive(Properties, PartialEq)]
+pub struct RequestStatusNoteProps {
+    pub requests: Requests,
+    /// The kinds of session message this note reports on
+    pub actions: &'static [&'static str],
+}
+
+/// A small inline note for actions that are still being sent or have failed.
+///
+/// Failures are shown before pending requests, and nothing is shown once all
+/// of the actions have been acknowledged.
+#[function_component(RequestStatusNote)]
+pub fn request_status_note(props: &RequestStatusNoteProps) -> Html {
+    let statuses: Vec<&RequestStatus> = props
+        .actions
+        .iter()
+        .filter_map(|action| props.requests.status(action))
+        .collect();
+
+    let failed = statuses.iter().find_map(|status| match status {
+        RequestStatus::Failed(message) => Some(message),
+        RequestStatus::Pending => None,
+    });
+
+    if let Some(message) = failed {
+        html! {
+            <p class="text-sm text-red-600 dark:text-red-400" role="alert">{ format!("⚠️ {message}") }</p>
+        }
+    } else if !statuses.is_empty() {
+        html! {
+            <p class="text-sm text-gray-500 dark:text-gray-400 italic">{ "Sending..." }</p>
+        }
+    } else {
+        html! {}
+    }
+}
@@ -0,0 +1,106 @@
+use yew::prelude::*;
+
+use kiko::{data::RoundResult, log::info};
+
+use crate::providers::{api, use_backend};
+
+const CARD_CLASSES: &str = "bg-white dark:bg-gray-800 border border-gray-200 dark:border-gray-700 rounded-xl p-6 shadow-sm";
+
+fn format_time(timestamp: u64) -> String {
+    let date = js_sys::Date::new(&((timestamp as f64) * 1000.0).into());
+    date.to_locale_time_string("en-US")
+        .as_string()
+        .unwrap_or_default()
+}
+
+// Only numeric cards count towards the average
+fn average_points(round: &RoundResult) -> Option<f64> {
+    let points: Vec<f64> = round
+        .votes()
+        .iter()
+        .filter_map(|v| v.points()?.parse::<f64>().ok())
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    Some(points.iter().sum::<f64>() / points.len() as f64)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RoundHistoryProps {
+    pub session_id: String,
+    /// When the current round started, the history is reloaded whenever it changes.
+    pub round_started: u64,
+}
+
+/// The session's completed rounds, loaded separately from the session updates.
+#[function_component(RoundHistory)]
+pub fn round_history(props: &RoundHistoryProps) -> Html {
+    let backend = use_backend();
+    let api = use_memo(backend, api::create);
+    let history = use_state(Vec::<RoundResult>::new);
+
+    {
+        let history = history.clone();
+        use_effect_with(
+            (props.session_id.clone(), props.round_started),
+            move |(session_id, _)| {
+                let session_id = session_id.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    match api.fetch_history(&session_id).await {
+                        Ok(rounds) => history.set(rounds),
+                        Err(err) => info!("❌ Failed to load round history: {:?}", err),
+                    }
+                });
+            },
+        );
+    }
+
+    if history.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class={CARD_CLASSES}>
+            <h3 class="text-lg font-semibold text-gray-900 dark:text-gray-100 mb-4">{ "Round History" }</h3>
+            <div class="space-y-3">
+                {
+                    history.iter().rev().map(|round| {
+                        let topic = if round.topic().is_empty() { "Untitled round" } else { round.topic() };
+                        html! {
+                            <div class="p-4 bg-gray-50 dark:bg-gray-700 rounded-lg">
+                                <div class="flex items-center justify-between mb-2">
+                                    <span class="text-sm font-medium text-gray-900 dark:text-gray-100">{ topic }</span>
+                                    <span class="text-xs text-gray-500 dark:text-gray-400">
+                                        { format!("{} – {}", format_time(round.started()), format_time(round.ended())) }
+                                    </span>
+                                </div>
+                                <div class="flex flex-wrap gap-2 text-xs">
+                                    {
+                                        round.votes().iter().map(|vote| {
+                                            let points = if vote.is_hidden() { "•••" } else { vote.points().unwrap_or("?") };
+                                            html! {
+                                                <span key={vote.id().to_string()} class="px-2 py-1 rounded-full bg-white dark:bg-gray-600 text-gray-700 dark:text-gray-200 border border-gray-200 dark:border-gray-500">
+                                                    { format!("{}: {}", vote.participant_name(), points) }
+                                                </span>
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                </div>
+                                <div class="mt-2 text-xs text-gray-500 dark:text-gray-400">
+                                    {
+                                        match average_points(round) {
+                                            Some(avg) => format!("Average: {avg:.1}"),
+                                            None => "Average: —".to_string(),
+                                        }
+                                    }
+                                    { if round.revealed() { "" } else { " · not revealed" } }
+                                </div>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        </div>
+    }
+}
@@ -3,11 +3,12 @@ use std::time::Duration;
 use web_sys::{InputEvent, KeyboardEvent, MouseEvent};
 use yew::prelude::*;
 
-use kiko::data::{Participant, PointSession, Session, SessionMessage};
-use kiko::serde_json;
+use kiko::data::{MAX_TOPIC_CHARS, Participant, PointSession, Session, SessionMessage};
 
-use crate::components::{ConnectionIndicator, CopyUrlButton};
-use crate::hooks::ConnectionState;
+use crate::components::{
+    ConnectionIndicator, CopyUrlButton, RequestStatusNote, RoundHistory, StoryQueue,
+};
+use crate::hooks::{ConnectionState, Reconnect, Requests};
 
 // CSS class constants
 const CARD_CLASSES: &str = "bg-white dark:bg-gray-800 border border-gray-200 dark:border-gray-700 rounded-xl p-6 shadow-sm";
@@ -15,20 +16,19 @@ const TEXT_PRIMARY: &str = "text-gray-900 dark:text-gray-100";
 const TEXT_SECONDARY: &str = "text-gray-600 dark:text-gray-400";
 // const GRID_LAYOUT: &str = "grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-3 gap-3";
 
-fn is_point_selected(selected_points: Option<u32>, points: u32) -> bool {
-    selected_points
-        == Some(points)
-            .filter(|&p| p != 0)
-            .or(if points == 0 { Some(0) } else { None })
+// Cards like "?", "☕" or "∞" get a different style from numbers and sizes
+fn is_special_card(card: &str) -> bool {
+    !card.chars().any(char::is_alphanumeric)
 }
 
 // Utility function for sending WebSocket messages
-fn send_session_message(sender: &Option<Callback<String>>, message: SessionMessage) -> bool {
+pub(crate) fn send_session_message(
+    sender: &Option<Callback<SessionMessage>>,
+    message: SessionMessage,
+) -> bool {
     if let Some(sender) = sender {
-        if let Ok(message_text) = serde_json::to_string(&message) {
-            sender.emit(message_text);
-            return true;
-        }
+        sender.emit(message);
+        return true;
     }
     false
 }
@@ -92,8 +92,11 @@ fn edit_button(props: &EditButtonProps) -> Html {
 pub struct SessionViewProps {
     pub session: Session,
     pub ws_state: ConnectionState,
+    pub ws_reconnect: Option<Reconnect>,
     pub on_refresh: Option<Callback<MouseEvent>>,
-    pub on_send_message: Option<Callback<String>>,
+    pub on_send_message: Option<Callback<SessionMessage>>,
+    /// Outcome of the messages sent through `on_send_message`
+    pub requests: Requests,
     pub participant_name: Option<String>,
     pub participant_id: Option<String>,
     pub is_joined: bool,
@@ -104,9 +107,15 @@ pub fn session_view(props: &SessionViewProps) -> Html {
     let session = &props.session;
     let ws_state = &props.ws_state;
     let topic_input = use_state(String::new);
-    let selected_points = use_state(|| None::<u32>);
+    let selected_points = use_state(|| None::<String>);
     let show_topic_input = use_state(|| false);
 
+    // Only the facilitator gets the controls for running the session
+    let is_facilitator = props
+        .participant_id
+        .as_ref()
+        .is_some_and(|id| session.is_facilitator(&id.clone().into()));
+
     // Sync selected_points with session state when points are cleared
     use_effect_with(
         (
@@ -116,7 +125,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
         {
             let selected_points = selected_points.clone();
             move |(session_points, participant_id_str): &(
-                HashMap<kiko::id::ParticipantId, Option<u32>>,
+                HashMap<kiko::id::ParticipantId, Option<String>>,
                 Option<String>,
             )| {
                 if let Some(id_str) = participant_id_str {
@@ -195,7 +204,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                     <div class="flex items-center justify-between">
                         <div class="flex items-center space-x-4">
                             <h1 class="text-xl font-semibold text-gray-900 dark:text-gray-100">{ session.name() }</h1>
-                            <ConnectionIndicator state={ws_state.clone()} />
+                            <ConnectionIndicator state={ws_state.clone()} reconnect={props.ws_reconnect.clone()} />
                             <span class={format!("inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium {}",
                                 if is_active { "bg-green-100 dark:bg-green-900/40 text-green-800 dark:text-green-300" } else { "bg-red-100 dark:bg-red-900/40 text-red-800 dark:text-red-300" })}>
                                 { if is_active { "Active" } else { "Ended" } }
@@ -264,21 +273,31 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                 <CopyUrlButton />
                             </div>
                         </div>
+
+                        <StoryQueue
+                            stories={session.stories().clone()}
+                            on_send_message={props.on_send_message.clone()}
+                            requests={props.requests.clone()}
+                            can_manage={is_facilitator}
+                        />
                     </div>
 
                     // Right Column - Participants & Voting
                     <div class="lg:col-span-3 space-y-6">
                         // Topic Section - Always show if there are participants
                         <div class="bg-white dark:bg-gray-800 border border-gray-200 dark:border-gray-700 rounded-xl p-6 mb-6 shadow-sm">
-                            <h3 class="text-lg font-semibold text-gray-900 dark:text-gray-100 mb-4">{ "Story Topic" }</h3>
+                            <div class="flex items-center justify-between mb-4">
+                                <h3 class="text-lg font-semibold text-gray-900 dark:text-gray-100">{ "Story Topic" }</h3>
+                                <RequestStatusNote requests={props.requests.clone()} actions={&["SetTopic"][..]} />
+                            </div>
                             {
                                 if !session.current_topic().is_empty() {
                                     html! {
                                         <div class={format!("bg-blue-50 dark:bg-blue-900/20 {} border border-blue-200 dark:border-blue-800 p-4 rounded-lg mb-4 relative group",
-                                            if props.is_joined { "flex justify-between" } else { "" })}>
+                                            if is_facilitator { "flex justify-between" } else { "" })}>
                                             <p class="text-blue-900 dark:text-blue-300 font-medium pr-8">{ session.current_topic() }</p>
                                             {
-                                                if props.is_joined {
+                                                if is_facilitator {
                                                     let toggle_topic_input = {
                                                         let show_topic_input = show_topic_input.clone();
                                                         Callback::from(move |_: MouseEvent| {
@@ -286,15 +305,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                         })
                                                     };
                                                     html! {
-                                                        <button
-                                                            class="p-1.5 text-white bg-blue-600 hover:bg-blue-700 rounded-md transition-colors focus:outline-none focus:ring-2 focus:ring-blue-500 opacity-0 group-hover:opacity-100"
-                                                            onclick={toggle_topic_input}
-                                                            title="Edit topic"
-                                                        >
-                                                            <svg class="h-3 w-3" fill="none" stroke="currentColor" viewBox="0 0 24 24">
-                                                                <path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M11 5H6a2 2 0 00-2 2v11a2 2 0 002 2h11a2 2 0 002-2v-5m-1.414-9.414a2 2 0 112.828 2.828L11.828 15H9v-2.828l8.586-8.586z" />
-                                                            </svg>
-                                                        </button>
+                                                        <EditButton onclick={toggle_topic_input} />
                                                     }
                                                 } else {
                                                     html! {}
@@ -305,10 +316,10 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                 } else {
                                     html! {
                                         <div class={format!("bg-gray-50 dark:bg-gray-700 {} border border-gray-200 dark:border-gray-600 p-4 rounded-lg mb-4 relative group",
-                                            if props.is_joined { "flex justify-between" } else { "" })}>
+                                            if is_facilitator { "flex justify-between" } else { "" })}>
                                             <p class="text-gray-500 dark:text-gray-400 italic pr-8">{ "No topic set yet" }</p>
                                             {
-                                                if props.is_joined {
+                                                if is_facilitator {
                                                     let toggle_topic_input = {
                                                         let show_topic_input = show_topic_input.clone();
                                                         Callback::from(move |_: MouseEvent| {
@@ -316,15 +327,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                         })
                                                     };
                                                     html! {
-                                                        <button
-                                                            class="p-1.5 text-white bg-gray-600 hover:bg-gray-700 dark:bg-gray-500 dark:hover:bg-gray-600 rounded-md transition-colors focus:outline-none focus:ring-2 focus:ring-gray-500 opacity-0 group-hover:opacity-100"
-                                                            onclick={toggle_topic_input}
-                                                            title="Edit topic"
-                                                        >
-                                                            <svg class="h-3 w-3" fill="none" stroke="currentColor" viewBox="0 0 24 24">
-                                                                <path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M11 5H6a2 2 0 00-2 2v11a2 2 0 002 2h11a2 2 0 002-2v-5m-1.414-9.414a2 2 0 112.828 2.828L11.828 15H9v-2.828l8.586-8.586z" />
-                                                            </svg>
-                                                        </button>
+                                                        <EditButton onclick={toggle_topic_input} color="gray" />
                                                     }
                                                 } else {
                                                     html! {}
@@ -335,7 +338,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                 }
                             }
                             {
-                                if props.is_joined && *show_topic_input {
+                                if is_facilitator && *show_topic_input {
                                     let on_topic_change = {
                                         let on_send_message = props.on_send_message.clone();
                                         let topic_input = topic_input.clone();
@@ -365,6 +368,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                 type="text"
                                                 class="flex-1 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
                                                 placeholder="Enter story to estimate..."
+                                                maxlength={MAX_TOPIC_CHARS.to_string()}
                                                 value={(*topic_input).clone()}
                                                 oninput={{
                                                     let topic_input = topic_input.clone();
@@ -410,27 +414,24 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                         // Voting Section - Show votes to everyone, but only allow interaction if joined
                         {
                             if !session.participants().is_empty() {
-                                let points_options = [1, 2, 3, 5, 8, 13, 21, 0]; // 0 for "I don't know"
+                                let cards = session.deck().cards();
 
                                 let on_point = {
                                     let on_send_message = props.on_send_message.clone();
                                     let participant_id = props.participant_id.clone();
                                     let session_id = session.id.clone();
                                     let selected_points = selected_points.clone();
-                                    Callback::from(move |points: u32| {
+                                    Callback::from(move |card: String| {
                                         // Update local state immediately
-                                        selected_points.set(Some(points));
+                                        selected_points.set(Some(card.clone()));
 
-                                        if let (Some(sender), Some(id_str)) = (&on_send_message, &participant_id) {
-                                            let point_value = if points == 0 { None } else { Some(points) };
+                                        if let Some(id_str) = &participant_id {
                                             let point_message = SessionMessage::PointSession(PointSession {
                                                 session_id: session_id.to_string(),
                                                 participant_id: id_str.clone(),
-                                                points: point_value,
+                                                points: Some(card),
                                             });
-                                            if let Ok(message_text) = serde_json::to_string(&point_message) {
-                                                sender.emit(message_text);
-                                            }
+                                            send_session_message(&on_send_message, point_message);
                                         }
                                     })
                                 };
@@ -457,8 +458,12 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                             <h3 class={classes!("text-xl", "font-semibold", TEXT_PRIMARY)}>
                                                 { if props.is_joined { "Choose Your Estimate" } else { "Voting" } }
                                             </h3>
+                                            <RequestStatusNote
+                                                requests={props.requests.clone()}
+                                                actions={&["PointSession", "ToggleHidePoints", "ClearPoints"][..]}
+                                            />
                                             {
-                                                if props.is_joined {
+                                                if is_facilitator {
                                                     html! {
                                                         <div class="flex items-center space-x-2">
                                                             <button
@@ -495,24 +500,25 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                 html! {
                                                     <div class="grid grid-cols-4 md:grid-cols-8 gap-3 mb-6">
                                                         {
-                                                            points_options.iter().map(|&points| {
+                                                            cards.iter().map(|card| {
                                                                 let point_callback = {
                                                                     let on_point = on_point.clone();
+                                                                    let card = card.clone();
                                                                     Callback::from(move |_: MouseEvent| {
-                                                                        on_point.emit(points);
+                                                                        on_point.emit(card.clone());
                                                                     })
                                                                 };
 
-                                                                let is_selected = is_point_selected(*selected_points, points);
+                                                                let is_selected = selected_points.as_deref() == Some(card.as_str());
 
                                                                 html! {
                                                                     <button
-                                                                        key={points.to_string()}
+                                                                        key={card.clone()}
                                                                         class={format!(
                                                                             "h-12 rounded-lg border-2 font-bold text-lg transition-all duration-200 transform hover:scale-105 focus:scale-105 {}",
                                                                             if is_selected {
                                                                                 "bg-blue-600 text-white border-blue-600 shadow-lg ring-4 ring-blue-200 dark:ring-blue-800"
-                                                                            } else if points == 0 {
+                                                                            } else if is_special_card(card) {
                                                                                 "bg-orange-50 dark:bg-orange-900/20 text-orange-700 dark:text-orange-300 border-orange-300 dark:border-orange-700 hover:bg-orange-100 dark:hover:bg-orange-800/40 hover:border-orange-400 dark:hover:border-orange-600"
                                                                             } else {
                                                                                 "bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 border-gray-300 dark:border-gray-600 hover:border-blue-400 hover:bg-blue-50 dark:hover:bg-gray-600 shadow-sm hover:shadow"
@@ -520,7 +526,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                                         )}
                                                                         onclick={point_callback}
                                                                     >
-                                                                        { if points == 0 { "?".to_string() } else { points.to_string() } }
+                                                                        { card }
                                                                     </button>
                                                                 }
                                                             }).collect::<Html>()
@@ -552,31 +558,30 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                             {
                                                                 session.participants().iter().map(|participant| {
                                                                     // Look up points for this participant
-                                                                    let has_voted = session.current_points().contains_key(participant.id());
-                                                                    let participant_points = if has_voted {
-                                                                        session.current_points().get(participant.id()).copied().unwrap()
-                                                                    } else {
-                                                                        None
-                                                                    };
+                                                                    // Other people's votes arrive redacted until they are revealed
+                                                                    let has_voted = session.has_voted(participant.id());
+                                                                    let participant_points = session.current_points().get(participant.id()).cloned().flatten();
                                                                     html! {
                                                                         <div key={participant.id().to_string()} class="flex items-center justify-between p-4 bg-gray-50 dark:bg-gray-700 rounded-lg">
                                                                             <div class="flex items-center space-x-3">
-                                                                                <div class="h-10 w-10 bg-blue-500 rounded-full flex items-center justify-center">
-                                                                                    <span class="text-sm font-medium text-white">
-                                                                                        { participant.name().chars().next().unwrap_or('?').to_uppercase().to_string() }
-                                                                                    </span>
-                                                                                </div>
+                                                                                <ParticipantAvatar participant={participant.clone()} />
                                                                                 <span class="text-sm font-medium text-gray-900 dark:text-gray-100">{ participant.name() }</span>
+                                                                                {
+                                                                                    if session.is_facilitator(participant.id()) {
+                                                                                        html! {
+                                                                                            <span class="px-2 py-0.5 rounded-full text-xs font-medium bg-purple-100 dark:bg-purple-900/40 text-purple-800 dark:text-purple-300">{ "Facilitator" }</span>
+                                                                                        }
+                                                                                    } else {
+                                                                                        html! {}
+                                                                                    }
+                                                                                }
                                                                             </div>
                                                                             <span class={format!(
                                                                                 "px-3 py-1 rounded-full text-sm font-bold {}",
-                                                                                if !has_voted || participant_points.is_none() {
-                                                                                    "bg-gray-200 dark:bg-gray-600 text-gray-700 dark:text-gray-300"
+                                                                                if has_voted && (participant_points.is_some() || session.hide_points()) {
+                                                                                    "bg-blue-100 dark:bg-blue-900/40 text-blue-800 dark:text-blue-300"
                                                                                 } else {
-                                                                                    match participant_points {
-                                                                                        Some(_) => "bg-blue-100 dark:bg-blue-900/40 text-blue-800 dark:text-blue-300",
-                                                                                        None => "bg-orange-100 dark:bg-orange-900/40 text-orange-800 dark:text-orange-300",
-                                                                                    }
+                                                                                    "bg-gray-200 dark:bg-gray-600 text-gray-700 dark:text-gray-300"
                                                                                 }
                                                                             )}>{
                                                                                 if !has_voted {
@@ -584,11 +589,7 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                                                                 } else if session.hide_points() {
                                                                                     "•••".to_string()
                                                                                 } else {
-                                                                                    match participant_points {
-                                                                                        Some(0) => "🤷‍♀️".to_string(),
-                                                                                        Some(p) => p.to_string(),
-                                                                                        None => "?".to_string(),
-                                                                                    }
+                                                                                    participant_points.unwrap_or_else(|| "?".to_string())
                                                                                 }
                                                                             }</span>
                                                                         </div>
@@ -608,6 +609,8 @@ pub fn session_view(props: &SessionViewProps) -> Html {
                                 html! {}
                             }
                         }
+
+                        <RoundHistory session_id={session.id.to_string()} round_started={session.round_started()} />
                     </div>
                 </div>
             </div>
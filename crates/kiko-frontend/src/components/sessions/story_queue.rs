@@ -0,0 +1,204 @@
+use web_sys::{InputEvent, KeyboardEvent, MouseEvent};
+use yew::prelude::*;
+
+use kiko::data::{MAX_TOPIC_CHARS, ReorderStory, SessionMessage, Story};
+
+use crate::components::{RequestStatusNote, sessions::session_view::send_session_message};
+use crate::hooks::Requests;
+
+const CARD_CLASSES: &str = "bg-white dark:bg-gray-800 border border-gray-200 dark:border-gray-700 rounded-xl p-6 shadow-sm";
+const ICON_BUTTON_CLASSES: &str = "p-1 text-gray-400 dark:text-gray-500 hover:text-gray-700 dark:hover:text-gray-200 rounded focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:opacity-30 disabled:cursor-not-allowed";
+
+#[derive(Properties, PartialEq)]
+pub struct StoryQueueProps {
+    pub stories: Vec<Story>,
+    pub on_send_message: Option<Callback<SessionMessage>>,
+    /// Outcome of the messages sent through `on_send_message`
+    pub requests: Requests,
+    /// Whether the current user may edit the queue (the facilitator)
+    pub can_manage: bool,
+}
+
+#[function_component(StoryQueue)]
+pub fn story_queue(props: &StoryQueueProps) -> Html {
+    let story_input = use_state(String::new);
+
+    let on_add_story = {
+        let on_send_message = props.on_send_message.clone();
+        let story_input = story_input.clone();
+        Callback::from(move |_: ()| {
+            let title = story_input.trim().to_string();
+            if title.is_empty() {
+                return;
+            }
+            if send_session_message(&on_send_message, SessionMessage::AddStory(title)) {
+                story_input.set(String::new());
+            }
+        })
+    };
+
+    let on_next_story = {
+        let on_send_message = props.on_send_message.clone();
+        Callback::from(move |_: MouseEvent| {
+            send_session_message(&on_send_message, SessionMessage::NextStory);
+        })
+    };
+
+    let move_story = {
+        let on_send_message = props.on_send_message.clone();
+        Callback::from(move |(story_id, position): (String, usize)| {
+            send_session_message(
+                &on_send_message,
+                SessionMessage::ReorderStory(ReorderStory { story_id, position }),
+            );
+        })
+    };
+
+    let remove_story = {
+        let on_send_message = props.on_send_message.clone();
+        Callback::from(move |story_id: String| {
+            send_session_message(&on_send_message, SessionMessage::RemoveStory(story_id));
+        })
+    };
+
+    let story_count = props.stories.len();
+
+    html! {
+        <div class={CARD_CLASSES}>
+            <div class="flex items-center justify-between mb-4">
+                <h3 class="text-lg font-semibold text-gray-900 dark:text-gray-100">{ "Story Queue" }</h3>
+                {
+                    if props.can_manage {
+                        html! {
+                            <button
+                                class="px-3 py-1 bg-blue-50 dark:bg-blue-800/60 text-blue-600 dark:text-blue-200 rounded-lg hover:bg-blue-100 dark:hover:bg-blue-700/70 focus:outline-none focus:ring-2 focus:ring-blue-500 text-sm transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+                                onclick={on_next_story}
+                                disabled={story_count == 0}
+                                title="Start estimating the next story"
+                            >
+                                { "⏭️ Next" }
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+
+            <div class="mb-2">
+                <RequestStatusNote
+                    requests={props.requests.clone()}
+                    actions={&["AddStory", "RemoveStory", "ReorderStory", "NextStory"][..]}
+                />
+            </div>
+
+            {
+                if props.stories.is_empty() {
+                    html! {
+                        <p class="text-sm text-gray-500 dark:text-gray-400 italic mb-4">{ "No stories queued" }</p>
+                    }
+                } else {
+                    html! {
+                        <ol class="space-y-2 mb-4">
+                            {
+                                props.stories.iter().enumerate().map(|(index, story)| {
+                                    let story_id = story.id().to_string();
+                                    html! {
+                                        <li key={story_id.clone()} class="flex items-center justify-between p-2 bg-gray-50 dark:bg-gray-700 rounded-lg">
+                                            <span class="text-sm text-gray-900 dark:text-gray-100 truncate">
+                                                <span class="text-gray-500 dark:text-gray-400 mr-2">{ format!("{}.", index + 1) }</span>
+                                                { story.title() }
+                                            </span>
+                                            {
+                                                if props.can_manage {
+                                                    html! {
+                                                        <div class="flex items-center space-x-1 flex-shrink-0">
+                                                            <button
+                                                                class={ICON_BUTTON_CLASSES}
+                                                                title="Move up"
+                                                                disabled={index == 0}
+                                                                onclick={move_story.reform({
+                                                                    let story_id = story_id.clone();
+                                                                    move |_: MouseEvent| (story_id.clone(), index.saturating_sub(1))
+                                                                })}
+                                                            >
+                                                                { "▲" }
+                                                            </button>
+                                                            <button
+                                                                class={ICON_BUTTON_CLASSES}
+                                                                title="Move down"
+                                                                disabled={index + 1 == story_count}
+                                                                onclick={move_story.reform({
+                                                                    let story_id = story_id.clone();
+                                                                    move |_: MouseEvent| (story_id.clone(), index + 1)
+                                                                })}
+                                                            >
+                                                                { "▼" }
+                                                            </button>
+                                                            <button
+                                                                class={ICON_BUTTON_CLASSES}
+                                                                title="Remove story"
+                                                                onclick={remove_story.reform({
+                                                                    let story_id = story_id.clone();
+                                                                    move |_: MouseEvent| story_id.clone()
+                                                                })}
+                                                            >
+                                                                { "✕" }
+                                                            </button>
+                                                        </div>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                        </li>
+                                    }
+                                }).collect::<Html>()
+                            }
+                        </ol>
+                    }
+                }
+            }
+
+            {
+                if props.can_manage {
+                    html! {
+                        <div class="flex space-x-2">
+                            <input
+                                type="text"
+                                class="flex-1 min-w-0 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                                placeholder="Add a story..."
+                                maxlength={MAX_TOPIC_CHARS.to_string()}
+                                value={(*story_input).clone()}
+                                oninput={{
+                                    let story_input = story_input.clone();
+                                    Callback::from(move |e: InputEvent| {
+                                        if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                                            story_input.set(input.value());
+                                        }
+                                    })
+                                }}
+                                onkeypress={{
+                                    let on_add_story = on_add_story.clone();
+                                    Callback::from(move |e: KeyboardEvent| {
+                                        if e.key() == "Enter" {
+                                            on_add_story.emit(());
+                                        }
+                                    })
+                                }}
+                            />
+                            <button
+                                class="px-3 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-blue-500 text-sm font-medium"
+                                onclick={on_add_story.reform(|_: MouseEvent| ())}
+                            >
+                                { "Add" }
+                            </button>
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
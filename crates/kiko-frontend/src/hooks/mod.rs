@@ -1,5 +1,7 @@
 //! Custom Yew hooks for state management and side effects.
 
+pub mod use_requests;
 pub mod use_websocket;
 
+pub use use_requests::*;
 pub use use_websocket::*;
@@ -0,0 +1,174 @@
+//! Request tracking hook for Yew components.
+//!
+//! This module wraps outgoing session messages in requests with an ID, and keeps
+//! track of which actions are still waiting for the server or have failed.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use kiko::{
+    data::{ClientMessage, SessionMessage},
+    log::error,
+    serde_json,
+};
+
+/// Where a request sent to the server currently stands.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RequestStatus {
+    /// Sent, waiting for the server to acknowledge it
+    Pending,
+    /// The server rejected it, or the connection was lost before it answered
+    Failed(String),
+}
+
+/// The latest request for each kind of action, keyed by [`SessionMessage::kind`].
+///
+/// Acknowledged requests are forgotten, so an action without an entry is settled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Requests {
+    by_action: HashMap<&'static str, (String, RequestStatus)>,
+}
+
+impl Requests {
+    /// Returns the status of the latest request for an action, if it hasn't settled.
+    pub fn status(&self, action: &str) -> Option<&RequestStatus> {
+        self.by_action.get(action).map(|(_, status)| status)
+    }
+
+    /// Returns true if the latest request for an action is still waiting.
+    pub fn is_pending(&self, action: &str) -> bool {
+        matches!(self.status(action), Some(RequestStatus::Pending))
+    }
+}
+
+/// Changes to the tracked requests.
+pub enum RequestAction {
+    Sent {
+        request_id: String,
+        action: &'static str,
+    },
+    Acknowledged(String),
+    Rejected {
+        request_id: String,
+        message: String,
+    },
+    ConnectionLost,
+}
+
+impl Reducible for Requests {
+    type Action = RequestAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut requests = (*self).clone();
+        match action {
+            RequestAction::Sent { request_id, action } => {
+                requests
+                    .by_action
+                    .insert(action, (request_id, RequestStatus::Pending));
+            }
+            RequestAction::Acknowledged(request_id) => {
+                requests.by_action.retain(|_, (id, _)| *id != request_id);
+            }
+            RequestAction::Rejected {
+                request_id,
+                message,
+            } => {
+                // Only the latest request for an action is shown
+                for (id, status) in requests.by_action.values_mut() {
+                    if *id == request_id {
+                        *status = RequestStatus::Failed(message.clone());
+                    }
+                }
+            }
+            RequestAction::ConnectionLost => {
+                for (_, status) in requests.by_action.values_mut() {
+                    if *status == RequestStatus::Pending {
+                        *status = RequestStatus::Failed("Connection lost".to_string());
+                    }
+                }
+            }
+        }
+        Rc::new(requests)
+    }
+}
+
+/// Handle returned by the `use_requests` hook.
+pub struct RequestsHandle {
+    /// The current state of all tracked actions
+    pub requests: Requests,
+    /// Callback to send a session message as a tracked request
+    pub send: Callback<SessionMessage>,
+    /// Callback for the server acknowledging a request ID
+    pub acknowledge: Callback<String>,
+    /// Callback for the server rejecting a request ID with an error message
+    pub reject: Callback<(String, String)>,
+    /// Callback to fail every pending request, e.g. when the connection drops
+    pub connection_lost: Callback<()>,
+}
+
+/// A Yew hook for sending session messages as requests and tracking their outcome.
+///
+/// # Arguments
+///
+/// * `send_text` - Sends raw text to the server, usually `use_websocket`'s `send`
+#[hook]
+pub fn use_requests(send_text: Callback<String>) -> RequestsHandle {
+    let requests = use_reducer(Requests::default);
+    let next_id = use_mut_ref(|| 0u64);
+
+    let send = {
+        let requests = requests.clone();
+        Callback::from(move |message: SessionMessage| {
+            let request_id = {
+                let mut next_id = next_id.borrow_mut();
+                *next_id += 1;
+                format!("r{next_id}")
+            };
+            let action = message.kind();
+
+            let request = ClientMessage::Request {
+                request_id: request_id.clone(),
+                message,
+            };
+            match serde_json::to_string(&request) {
+                Ok(text) => {
+                    requests.dispatch(RequestAction::Sent { request_id, action });
+                    send_text.emit(text);
+                }
+                Err(err) => error!("Failed to serialize {action} request: {err}"),
+            }
+        })
+    };
+
+    let acknowledge = {
+        let requests = requests.clone();
+        Callback::from(move |request_id: String| {
+            requests.dispatch(RequestAction::Acknowledged(request_id));
+        })
+    };
+
+    let reject = {
+        let requests = requests.clone();
+        Callback::from(move |(request_id, message): (String, String)| {
+            requests.dispatch(RequestAction::Rejected {
+                request_id,
+                message,
+            });
+        })
+    };
+
+    let connection_lost = {
+        let requests = requests.clone();
+        Callback::from(move |_: ()| requests.dispatch(RequestAction::ConnectionLost))
+    };
+
+    RequestsHandle {
+        requests: (*requests).clone(),
+        send,
+        acknowledge,
+        reject,
+        connection_lost,
+    }
+}
@@ -1,19 +1,30 @@
 //! WebSocket hook for Yew components.
 //!
 //! This module provides a hook for managing WebSocket connections in Yew applications.
-//! It handles connection state, message sending/receiving, and automatic cleanup.
+//! It handles connection state, message sending/receiving, automatic reconnection
+//! and cleanup.
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use futures::stream::SplitSink;
+use futures::channel::mpsc;
 use futures::{SinkExt, StreamExt};
-use gloo_net::websocket::{Message, futures::WebSocket};
+use gloo_net::websocket::{Message, State, futures::WebSocket};
+use gloo_timers::callback::Timeout;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
 use kiko::async_callback;
 
+/// Delay before the first reconnection attempt, doubled for every further attempt.
+const INITIAL_RETRY_DELAY_MS: f64 = 500.0;
+
+/// Upper bound for the delay between reconnection attempts.
+const MAX_RETRY_DELAY_MS: f64 = 30_000.0;
+
+/// How long a connection must stay up before the backoff starts over.
+const STABLE_CONNECTION_MS: f64 = 10_000.0;
+
 /// Represents the current state of a WebSocket connection.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ConnectionState {
@@ -27,11 +38,23 @@ pub enum ConnectionState {
     Error(String),
 }
 
+/// Progress of automatic reconnection after a connection was lost.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reconnect {
+    /// How many reconnection attempts have been scheduled since the connection was lost
+    pub attempt: u32,
+    /// When the next attempt will be made, in milliseconds since the Unix epoch
+    pub next_retry_at: f64,
+}
+
 /// Callback type for handling incoming WebSocket messages.
 pub type MessageCallback = Callback<String>;
 
 /// Internal type for managing the WebSocket sender.
-type WebSocketSender = Rc<RefCell<Option<SplitSink<WebSocket, Message>>>>;
+///
+/// Messages are queued and written by a separate task, so sends never get lost
+/// while a previous one is still in flight.
+type WebSocketSender = Rc<RefCell<Option<mpsc::UnboundedSender<String>>>>;
 
 /// Internal type for managing the abort handle for the read task
 type AbortHandle = Rc<RefCell<Option<futures::future::AbortHandle>>>;
@@ -40,6 +63,8 @@ type AbortHandle = Rc<RefCell<Option<futures::future::AbortHandle>>>;
 pub struct WebSocketHandle {
     /// Current connection state
     pub state: ConnectionState,
+    /// Set while waiting to reconnect after the connection was lost
+    pub reconnect: Option<Reconnect>,
     /// Callback to initiate a WebSocket connection
     pub connect: Callback<()>,
     /// Callback to close the WebSocket connection
@@ -50,19 +75,33 @@ pub struct WebSocketHandle {
     pub set_on_message: Callback<MessageCallback>,
 }
 
+/// Returns how long to wait before the given reconnection attempt.
+///
+/// The delay doubles with every attempt, and is randomly shortened by up to half
+/// so clients that lost their connection at the same time don't retry in lockstep.
+fn retry_delay_ms(attempt: u32) -> f64 {
+    let backoff = INITIAL_RETRY_DELAY_MS * 2f64.powi(attempt.saturating_sub(1).min(16) as i32);
+    let delay = backoff.min(MAX_RETRY_DELAY_MS);
+    delay / 2.0 + js_sys::Math::random() * delay / 2.0
+}
+
 /// A Yew hook for managing WebSocket connections.
 ///
 /// This hook provides a complete WebSocket client implementation with automatic
 /// connection management, message handling, and state tracking.
 ///
+/// Once connected, a lost connection is retried with exponential backoff and jitter
+/// until it succeeds or `disconnect()` is called.
+///
 /// # Arguments
 ///
-/// * `url` - The WebSocket URL to connect to (e.g., "ws://localhost:3030/ws")
+/// * `url` - The WebSocket URL to connect to (e.g., "ws://localhost:3030/api/v1/ws")
 ///
 /// # Returns
 ///
 /// Returns a `WebSocketHandle` with methods to control the connection:
 /// - `state`: Current connection state
+/// - `reconnect`: Attempt count and next retry time while reconnecting
 /// - `connect()`: Initiate connection
 /// - `disconnect()`: Close connection
 /// - `send(message)`: Send text message
@@ -122,44 +161,92 @@ pub struct WebSocketHandle {
 /// 3. **Connected**: WebSocket ready for sending/receiving messages
 /// 4. **Error**: Connection failed or encountered an error
 ///
+/// From **Disconnected** or **Error** the hook goes back to **Connecting** on its own,
+/// unless the connection was closed with `disconnect()`.
+///
 /// The hook automatically handles connection cleanup when the component unmounts.
 #[hook]
 pub fn use_websocket(url: &str) -> WebSocketHandle {
     use futures::FutureExt;
 
     let state = use_state(|| ConnectionState::Disconnected);
+    let reconnect = use_state(|| None::<Reconnect>);
     let sender: WebSocketSender = use_mut_ref(|| None);
     let on_message_callback: Rc<RefCell<Option<MessageCallback>>> = use_mut_ref(|| None);
     let abort_handle: AbortHandle = use_mut_ref(|| None);
 
+    // Reconnection bookkeeping, shared with the read task
+    let should_reconnect = use_mut_ref(|| false);
+    let attempts = use_mut_ref(|| 0u32);
+    let connected_at = use_mut_ref(|| None::<f64>);
+    let retry_timer = use_mut_ref(|| None::<Timeout>);
+    // The retry timer needs the `connect` from the latest render, not the one it was created in
+    let latest_connect: Rc<RefCell<Option<Callback<()>>>> = use_mut_ref(|| None);
+
     let url = url.to_string();
 
     // Clean up on unmount
     {
         let abort_handle = abort_handle.clone();
         let sender = sender.clone();
+        let should_reconnect = should_reconnect.clone();
+        let retry_timer = retry_timer.clone();
         use_effect_with((), move |_| {
             move || {
+                *should_reconnect.borrow_mut() = false;
+                // Dropping the timer cancels a pending reconnection attempt
+                retry_timer.borrow_mut().take();
                 // Abort the read task if it exists
                 if let Some(handle) = abort_handle.borrow_mut().take() {
                     handle.abort();
                 }
-                // Close the sender if it exists
-                if let Some(mut write) = sender.borrow_mut().take() {
-                    spawn_local(async move {
-                        let _ = write.close().await;
-                    });
-                }
+                // Dropping the sender ends the write task, which closes the socket
+                sender.borrow_mut().take();
             }
         });
     }
 
-    let connect = async_callback!([state, sender, on_message_callback, abort_handle, url] {
+    // Schedules the next reconnection attempt, unless the connection was closed on purpose
+    let schedule_retry = {
+        let reconnect = reconnect.clone();
+        let should_reconnect = should_reconnect.clone();
+        let attempts = attempts.clone();
+        let retry_timer = retry_timer.clone();
+        let latest_connect = latest_connect.clone();
+
+        Rc::new(move || {
+            if !*should_reconnect.borrow() {
+                return;
+            }
+
+            let attempt = {
+                let mut attempts = attempts.borrow_mut();
+                *attempts += 1;
+                *attempts
+            };
+            let delay = retry_delay_ms(attempt);
+            reconnect.set(Some(Reconnect {
+                attempt,
+                next_retry_at: js_sys::Date::now() + delay,
+            }));
+
+            let latest_connect = latest_connect.clone();
+            *retry_timer.borrow_mut() = Some(Timeout::new(delay as u32, move || {
+                if let Some(connect) = latest_connect.borrow().as_ref() {
+                    connect.emit(());
+                }
+            }));
+        })
+    };
+
+    let connect = async_callback!([state, reconnect, sender, on_message_callback, abort_handle, url, should_reconnect, attempts, connected_at, retry_timer, schedule_retry] {
         // Don't connect if already connected or connecting
         if matches!(*state, ConnectionState::Connected | ConnectionState::Connecting) {
             return;
         }
 
+        *should_reconnect.borrow_mut() = true;
+        retry_timer.borrow_mut().take();
         state.set(ConnectionState::Connecting);
 
         // Abort any existing read task
@@ -168,11 +255,10 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
         }
 
         match WebSocket::open(&url) {
-            Ok(ws) => {
-                // Keep in Connecting state until we confirm the connection works
-
-                let (write, read) = ws.split();
-                *sender.borrow_mut() = Some(write);
+            Ok(mut ws) => {
+                // Queue outgoing messages until the socket has opened
+                let (tx, mut rx) = mpsc::unbounded::<String>();
+                *sender.borrow_mut() = Some(tx);
 
                 // Create an abortable future for the read task
                 let (abort_handle_new, abort_registration) = futures::future::AbortHandle::new_pair();
@@ -180,11 +266,28 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
 
                 // Handle incoming messages with ping health checks
                 let read_future = async move {
-                    let mut read = read;
-                    // Start with connection as Connected since WebSocket.open() succeeded
-                    // We'll detect failures through the read loop ending or send failures
-                    state.set(ConnectionState::Connected);
+                    // Stay Connecting until the handshake is done, a failed one wakes us up too
+                    let ready = futures::future::poll_fn(|cx| ws.poll_ready_unpin(cx)).await;
+                    if ready.is_ok() && matches!(ws.state(), State::Open) {
+                        state.set(ConnectionState::Connected);
+                        reconnect.set(None);
+                        *connected_at.borrow_mut() = Some(js_sys::Date::now());
+                    }
+
+                    let (mut write, mut read) = ws.split();
+                    spawn_local(async move {
+                        while let Some(text) = rx.next().await {
+                            if write.send(Message::Text(text)).await.is_err() {
+                                // Handle send error - connection might be closed
+                                use kiko::log::error;
+                                error!("Failed to send message through WebSocket");
+                                break;
+                            }
+                        }
+                        let _ = write.close().await;
+                    });
 
+                    // A socket that never opened ends the loop with its error or close
                     while let Some(msg) = read.next().await {
                         match msg {
                             Ok(Message::Text(text)) => {
@@ -210,6 +313,16 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
                     *sender.borrow_mut() = None;
                     *abort_handle.borrow_mut() = None;
                     state.set(ConnectionState::Disconnected);
+
+                    // A connection that held up for a while starts the backoff over
+                    let connected_for = connected_at
+                        .borrow_mut()
+                        .take()
+                        .map(|since| js_sys::Date::now() - since);
+                    if connected_for.is_some_and(|ms| ms >= STABLE_CONNECTION_MS) {
+                        *attempts.borrow_mut() = 0;
+                    }
+                    schedule_retry();
                 };
 
                 // Spawn the abortable future
@@ -217,32 +330,36 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
             }
             Err(e) => {
                 state.set(ConnectionState::Error(format!("Failed to connect: {e:?}")));
+                schedule_retry();
             }
         }
     });
+    *latest_connect.borrow_mut() = Some(connect.clone());
 
     let disconnect = {
         let state = state.clone();
+        let reconnect = reconnect.clone();
         let sender = sender.clone();
         let abort_handle = abort_handle.clone();
+        let should_reconnect = should_reconnect.clone();
+        let attempts = attempts.clone();
+        let retry_timer = retry_timer.clone();
 
         Callback::from(move |_: ()| {
+            // Closed on purpose, so don't come back
+            *should_reconnect.borrow_mut() = false;
+            *attempts.borrow_mut() = 0;
+            retry_timer.borrow_mut().take();
+            reconnect.set(None);
+
             // First, abort the read task to stop receiving messages
             if let Some(handle) = abort_handle.borrow_mut().take() {
                 handle.abort();
             }
 
-            // Then close the write half
-            if let Some(mut write) = sender.borrow_mut().take() {
-                let state = state.clone();
-                spawn_local(async move {
-                    let _ = write.close().await;
-                    state.set(ConnectionState::Disconnected);
-                });
-            } else {
-                // Already disconnected
-                state.set(ConnectionState::Disconnected);
-            }
+            // Then drop the sender, which makes the write task close the socket
+            sender.borrow_mut().take();
+            state.set(ConnectionState::Disconnected);
         })
     };
 
@@ -258,17 +375,11 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
                 return;
             }
 
-            let sender = sender.clone();
-            if let Some(mut write) = sender.borrow_mut().take() {
-                let sender_clone = sender.clone();
-                spawn_local(async move {
-                    if (write.send(Message::Text(text)).await).is_err() {
-                        // Handle send error - connection might be closed
-                        use kiko::log::error;
-                        error!("Failed to send message through WebSocket");
-                    }
-                    *sender_clone.borrow_mut() = Some(write);
-                });
+            if let Some(tx) = sender.borrow().as_ref()
+                && tx.unbounded_send(text).is_err()
+            {
+                use kiko::log::error;
+                error!("Failed to queue message for WebSocket");
             }
         })
     };
@@ -283,6 +394,7 @@ pub fn use_websocket(url: &str) -> WebSocketHandle {
 
     WebSocketHandle {
         state: (*state).clone(),
+        reconnect: (*reconnect).clone(),
         connect,
         disconnect,
         send,
@@ -9,22 +9,34 @@ mod pages;
 mod providers;
 mod routes;
 
-use providers::ThemeProvider;
+use providers::{BackendConfig, ThemeProvider};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+#[derive(Properties, PartialEq)]
+struct AppProps {
+    backend: BackendConfig,
+}
+
 #[function_component(App)]
-fn app() -> Html {
+fn app(props: &AppProps) -> Html {
     html! {
-        <ThemeProvider>
-            <BrowserRouter>
-                <Switch<routes::Route> render={routes::switch} />
-            </BrowserRouter>
-        </ThemeProvider>
+        <ContextProvider<BackendConfig> context={props.backend.clone()}>
+            <ThemeProvider>
+                <BrowserRouter>
+                    <Switch<routes::Route> render={routes::switch} />
+                </BrowserRouter>
+            </ThemeProvider>
+        </ContextProvider<BackendConfig>>
     }
 }
 
 fn main() {
     kiko::log::setup().expect("Failed to setup logging");
-    yew::Renderer::<App>::new().render();
+
+    // Find the backend before rendering, every page needs it
+    wasm_bindgen_futures::spawn_local(async {
+        let backend = BackendConfig::load().await;
+        yew::Renderer::<App>::with_props(AppProps { backend }).render();
+    });
 }
@@ -1,19 +1,31 @@
+use gloo_storage::{LocalStorage, SessionStorage, Storage};
 use web_sys::{InputEvent, KeyboardEvent, MouseEvent};
 use yew::prelude::*;
 
 use kiko::{
     async_callback,
-    data::{JoinSession, Session, SessionMessage, SubscribeToSession},
+    data::{
+        ClientMessage, ErrorCode, Hello, JoinSession, MAX_PARTICIPANT_NAME_CHARS, PROTOCOL_VERSION,
+        ResumeSession, ServerMessage, Session, SessionEndReason, SessionMessage,
+        SubscribeToSession, validate_participant_name,
+    },
     log::info,
     serde_json,
 };
 
 use crate::{
-    components::SessionView,
-    hooks::{ConnectionState, use_websocket},
-    providers::{ConfettiProvider, api, confetti::use_confetti},
+    components::{RequestStatusNote, SessionView, facilitator_key},
+    hooks::{ConnectionState, use_requests, use_websocket},
+    providers::{ConfettiProvider, api, confetti::use_confetti, use_backend},
 };
 
+/// Session storage key for the resume token and name of this tab's participant.
+///
+/// Session storage is per tab, so every tab still joins as its own participant.
+fn resume_key(session_id: &str) -> String {
+    format!("kiko.resume.{session_id}")
+}
+
 #[derive(Properties, PartialEq)]
 pub struct SessionProps {
     pub id: String,
@@ -22,7 +34,8 @@ pub struct SessionProps {
 #[function_component(SessionPageInner)]
 fn session_page_inner(props: &SessionProps) -> Html {
     let confetti = use_confetti();
-    let api = use_memo((), |_| api::create());
+    let backend = use_backend();
+    let api = use_memo(backend.clone(), api::create);
     let session_data = use_state(|| None::<Session>);
     let loading = use_state(|| true);
     let error_msg = use_state(|| None::<String>);
@@ -31,11 +44,18 @@ fn session_page_inner(props: &SessionProps) -> Html {
     let session_exists = use_state(|| false);
     let participant_name = use_state(String::new);
     let participant_id = use_state(|| None::<String>);
+    let resume_token = use_state(|| None::<String>);
     let is_joined = use_state(|| false);
     let is_subscribed = use_state(|| false);
+    let ended_reason = use_state(|| None::<SessionEndReason>);
+    // Set when the server no longer speaks our protocol version
+    let needs_reload = use_state(|| false);
 
     // WebSocket connection
-    let ws = use_websocket("ws://localhost:3030/api/v1/ws");
+    let ws = use_websocket(&backend.ws_url);
+
+    // Messages sent as requests, so each action can show whether it went through
+    let requests = use_requests(ws.send.clone());
 
     let session_id = props.id.clone();
 
@@ -86,7 +106,15 @@ fn session_page_inner(props: &SessionProps) -> Html {
         let confetti = confetti.clone();
         let participant_name = participant_name.clone();
         let participant_id = participant_id.clone();
+        let resume_token = resume_token.clone();
         let is_joined = is_joined.clone();
+        let is_subscribed = is_subscribed.clone();
+        let session_id = session_id.clone();
+        let ended_reason = ended_reason.clone();
+        let acknowledge = requests.acknowledge.clone();
+        let reject = requests.reject.clone();
+        let needs_reload = needs_reload.clone();
+        let ws_disconnect = ws.disconnect.clone();
 
         use_effect(move || {
             let message_callback = Callback::from({
@@ -95,7 +123,15 @@ fn session_page_inner(props: &SessionProps) -> Html {
                 let confetti = confetti.clone();
                 let participant_name = participant_name.clone();
                 let participant_id = participant_id.clone();
+                let resume_token = resume_token.clone();
                 let is_joined = is_joined.clone();
+                let is_subscribed = is_subscribed.clone();
+                let session_id = session_id.clone();
+                let ended_reason = ended_reason.clone();
+                let acknowledge = acknowledge.clone();
+                let reject = reject.clone();
+                let needs_reload = needs_reload.clone();
+                let ws_disconnect = ws_disconnect.clone();
                 move |text: String| {
                     info!("📨 Received WebSocket message: {}", text);
 
@@ -104,54 +140,87 @@ fn session_page_inner(props: &SessionProps) -> Html {
                         ws_error.set(None);
                     }
 
-                    // Check if this is an error message from the server
-                    if text.starts_with("Session ") && text.contains("not found") {
-                        ws_error.set(Some(text));
-                        return;
-                    }
-
-                    if text.contains("Invalid message format")
-                        || text.contains("Already subscribed")
-                    {
-                        ws_error.set(Some(text));
-                        return;
-                    }
-
-                    // Try to parse as SessionMessage
-                    match serde_json::from_str::<SessionMessage>(&text) {
-                        Ok(SessionMessage::SessionUpdate(updated_session)) => {
+                    match serde_json::from_str::<ServerMessage>(&text) {
+                        Ok(ServerMessage::Welcome(welcome)) => {
+                            info!(
+                                "🤝 Server speaks protocol version {}",
+                                welcome.protocol_version
+                            );
+                        }
+                        // Reconnecting won't help, only a fresh copy of the app will
+                        Ok(ServerMessage::Error {
+                            code: ErrorCode::IncompatibleProtocol,
+                            message,
+                            ..
+                        }) => {
+                            info!("❌ Incompatible protocol: {}", message);
+                            needs_reload.set(true);
+                            ws_disconnect.emit(());
+                        }
+                        // Our participant is gone, subscribe again and join anew if we were joined
+                        Ok(ServerMessage::Error {
+                            code: ErrorCode::ResumeRejected,
+                            ..
+                        }) => {
+                            info!("🆔 Could not resume, subscribing again");
+                            SessionStorage::delete(resume_key(&session_id));
+                            participant_id.set(None);
+                            resume_token.set(None);
+                            is_subscribed.set(false);
+                        }
+                        Ok(ServerMessage::Error {
+                            code,
+                            message,
+                            request_id,
+                        }) => {
+                            info!("❌ Server error ({:?}): {}", code, message);
+                            // Errors for a request are shown next to the action that sent it
+                            match request_id {
+                                Some(request_id) => reject.emit((request_id, message)),
+                                None => ws_error.set(Some(message)),
+                            }
+                        }
+                        Ok(ServerMessage::Ack { request_id }) => {
+                            acknowledge.emit(request_id);
+                        }
+                        Ok(ServerMessage::Session(SessionMessage::SessionUpdate(
+                            updated_session,
+                        ))) => {
                             info!("🔄 Session update received");
 
-                            // If we just joined and don't have a participant ID yet, find it
-                            if *is_joined
-                                && participant_id.is_none()
-                                && !participant_name.trim().is_empty()
-                            {
-                                // Look for the most recently added participant with our name
-                                // (assumes the backend adds participants in order)
-                                if let Some(participant) = updated_session
-                                    .participants()
-                                    .iter()
-                                    .filter(|p| p.name() == participant_name.trim())
-                                    .next_back()
-                                // Get the last (most recent) participant with this name
-                                {
-                                    info!("🆔 Found our participant ID: {}", participant.id());
-                                    participant_id.set(Some(participant.id().to_string()));
-                                }
+                            // A snapshot older than the one we show would undo newer changes
+                            if session_data.as_ref().is_some_and(|current| {
+                                current.version() > updated_session.version()
+                            }) {
+                                return;
                             }
 
                             // Check if points were revealed (hide_points changed from true to false)
-                            if let Some(current_session) = session_data.as_ref() {
-                                if current_session.hide_points() && !updated_session.hide_points() {
-                                    info!("🎉 Points revealed - triggering confetti!");
-                                    confetti.trigger.emit(());
-                                }
+                            if let Some(current_session) = session_data.as_ref()
+                                && current_session.hide_points()
+                                && !updated_session.hide_points()
+                            {
+                                info!("🎉 Points revealed - triggering confetti!");
+                                confetti.trigger.emit(());
                             }
 
-                            session_data.set(Some(updated_session));
+                            session_data.set(Some(*updated_session));
+                        }
+                        Ok(ServerMessage::Session(SessionMessage::Joined(joined))) => {
+                            info!("🆔 Joined as participant: {}", joined.participant_id);
+                            let stored = (joined.resume_token.clone(), (*participant_name).clone());
+                            if let Err(err) = SessionStorage::set(resume_key(&session_id), stored) {
+                                info!("❌ Failed to store resume token: {err:?}");
+                            }
+                            participant_id.set(Some(joined.participant_id));
+                            resume_token.set(Some(joined.resume_token));
+                            is_joined.set(true);
+                        }
+                        Ok(ServerMessage::Session(SessionMessage::SessionEnded(reason))) => {
+                            info!("🏁 Session ended: {:?}", reason);
+                            ended_reason.set(Some(reason));
                         }
-                        Ok(other_msg) => {
+                        Ok(ServerMessage::Session(other_msg)) => {
                             info!("📥 Other message type received: {:?}", other_msg);
                         }
                         Err(err) => {
@@ -171,31 +240,73 @@ fn session_page_inner(props: &SessionProps) -> Html {
         let ws_connect = ws.connect.clone();
         let ws_send = ws.send.clone();
         let ws_state = ws.state.clone();
+        let ws_retrying = ws.reconnect.is_some();
+        let needs_reload = *needs_reload;
         let session_exists = *session_exists;
         let is_subscribed = is_subscribed.clone();
         let session_id = session_id.clone();
+        let participant_name = participant_name.clone();
+        let is_joined = *is_joined;
         let ws_error = ws_error.clone();
+        let send_request = requests.send.clone();
+        let connection_lost = requests.connection_lost.clone();
 
         use_effect_with(
-            (ws_state.clone(), session_exists, *is_subscribed),
-            move |(state, exists, subscribed)| {
-                // Only attempt websocket connection and subscription if session exists and not already subscribed
-                if !*exists || *subscribed {
+            (
+                ws_state.clone(),
+                session_exists,
+                *is_subscribed,
+                ws_retrying,
+            ),
+            move |(state, exists, subscribed, retrying)| {
+                // The subscription is lost with the connection, so subscribe again once reconnected
+                if *subscribed {
+                    if state != &ConnectionState::Connected {
+                        is_subscribed.set(false);
+                        connection_lost.emit(());
+                    }
                     return;
                 }
 
-                if state == &ConnectionState::Disconnected {
+                // Only attempt websocket connection and subscription if session exists,
+                // and never against a server that already turned our protocol down
+                if !*exists || needs_reload {
+                    return;
+                }
+
+                // While the hook is waiting to reconnect, leave the timing to it
+                if state == &ConnectionState::Disconnected && !*retrying {
                     info!("🔌 Auto-connecting to WebSocket for session updates...");
                     ws_connect.emit(());
                 } else if state == &ConnectionState::Connected {
                     info!("📡 Auto-subscribing to session for updates...");
                     ws_error.set(None);
 
-                    // Send subscribe message for observation
-                    let subscribe_message =
-                        SessionMessage::SubscribeToSession(SubscribeToSession {
+                    // Every connection starts by telling the server which protocol we speak
+                    let hello = ClientMessage::Hello(Hello {
+                        protocol_version: PROTOCOL_VERSION,
+                        client_name: concat!("kiko-frontend/", env!("CARGO_PKG_VERSION"))
+                            .to_string(),
+                    });
+                    if let Ok(message_text) = serde_json::to_string(&hello) {
+                        ws_send.emit(message_text);
+                    }
+
+                    // Resume our participant if this tab joined before, otherwise just observe
+                    let stored = SessionStorage::get::<(String, String)>(resume_key(&session_id));
+                    let subscribe_message = match stored {
+                        Ok((resume_token, name)) => {
+                            info!("🆔 Resuming participant {}", name);
+                            participant_name.set(name);
+                            SessionMessage::ResumeSession(ResumeSession {
+                                session_id: session_id.clone(),
+                                resume_token,
+                            })
+                        }
+                        Err(_) => SessionMessage::SubscribeToSession(SubscribeToSession {
                             session_id: session_id.clone(),
-                        });
+                        }),
+                    };
 
                     if let Ok(message_text) = serde_json::to_string(&subscribe_message) {
                         ws_send.emit(message_text);
@@ -204,6 +315,18 @@ fn session_page_inner(props: &SessionProps) -> Html {
                     } else {
                         ws_error.set(Some("Failed to serialize subscribe message".to_string()));
                     }
+
+                    // We were joined but can't resume, so join again under the same name
+                    if is_joined
+                        && matches!(subscribe_message, SessionMessage::SubscribeToSession(_))
+                    {
+                        info!("👥 Rejoining session as {}", participant_name.trim());
+                        send_request.emit(SessionMessage::JoinSession(JoinSession {
+                            session_id: session_id.clone(),
+                            participant_name: participant_name.trim().to_string(),
+                            facilitator_token: LocalStorage::get(facilitator_key(&session_id)).ok(),
+                        }));
+                    }
                 }
             },
         );
@@ -211,24 +334,32 @@ fn session_page_inner(props: &SessionProps) -> Html {
 
     // Join session callback (for participation - separate from observation)
     let join_session = {
-        let ws_send = ws.send.clone();
+        let send_request = requests.send.clone();
+        let join_pending = requests.requests.is_pending("JoinSession");
         let ws_state = ws.state.clone();
         let session_id = session_id.clone();
         let participant_name = participant_name.clone();
         let is_joined = is_joined.clone();
         let ws_error = ws_error.clone();
 
-        async_callback!([ws_send, ws_state, session_id, participant_name, is_joined, ws_error] {
-            if participant_name.trim().is_empty() {
-                ws_error.set(Some("Please enter your name".to_string()));
-                return;
-            }
+        async_callback!([send_request, ws_state, session_id, participant_name, is_joined, ws_error] {
+            let name = match validate_participant_name(&participant_name) {
+                Ok(name) => name,
+                Err(error) => {
+                    ws_error.set(Some(error.to_string()));
+                    return;
+                }
+            };
 
             if *is_joined {
                 ws_error.set(Some("Already joined this session".to_string()));
                 return;
             }
 
+            if join_pending {
+                return;
+            }
+
             // Check if WebSocket is connected
             if !matches!(ws_state, ConnectionState::Connected) {
                 ws_error.set(Some("WebSocket not connected. Please wait and try again.".to_string()));
@@ -238,19 +369,15 @@ fn session_page_inner(props: &SessionProps) -> Html {
             ws_error.set(None);
 
             // Send join message for participation (this adds the participant to the session)
+            // We count as joined once the server answers with our participant ID
             info!("👥 Joining session as participant...");
-            let join_message = SessionMessage::JoinSession(JoinSession {
+            // The session's creator has its facilitator token, and joins as the facilitator
+            send_request.emit(SessionMessage::JoinSession(JoinSession {
                 session_id: session_id.clone(),
-                participant_name: participant_name.trim().to_string(),
-            });
-
-            if let Ok(message_text) = serde_json::to_string(&join_message) {
-                ws_send.emit(message_text);
-                info!("👥 Sent join message for session: {}", session_id);
-                is_joined.set(true);
-            } else {
-                ws_error.set(Some("Failed to serialize join message".to_string()));
-            }
+                participant_name: name,
+                facilitator_token: LocalStorage::get(facilitator_key(&session_id)).ok(),
+            }));
+            info!("👥 Sent join message for session: {}", session_id);
         })
     };
 
@@ -315,6 +442,36 @@ fn session_page_inner(props: &SessionProps) -> Html {
                 }
             }
 
+            // Show a reload prompt when the server no longer speaks our protocol
+            {
+                if *needs_reload {
+                    html! {
+                        <div class="mb-4 bg-blue-50 dark:bg-blue-950/20 border-b-2 border-blue-200 dark:border-blue-900 p-4 md:p-6">
+                            <div class="flex items-start mx-auto max-w-7xl">
+                                <div class="ml-3">
+                                    <h3 class="text-sm font-medium text-blue-800 dark:text-blue-300">{ "A new version of Kiko is available" }</h3>
+                                    <p class="text-sm text-blue-700 dark:text-blue-400 mt-1">{ "Please reload the page to keep receiving session updates." }</p>
+                                    <div class="mt-2">
+                                        <button
+                                            class="text-sm bg-blue-100 dark:bg-blue-800 hover:bg-blue-200 dark:hover:bg-blue-700 text-blue-800 dark:text-blue-200 px-3 py-1 rounded-md transition-colors"
+                                            onclick={Callback::from(|_| {
+                                                if let Some(window) = web_sys::window() {
+                                                    let _ = window.location().reload();
+                                                }
+                                            })}
+                                        >
+                                            { "Reload" }
+                                        </button>
+                                    </div>
+                                </div>
+                            </div>
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+
             // Show connection error details for Error state
             {
                 if let ConnectionState::Error(err) = &ws.state {
@@ -354,7 +511,27 @@ fn session_page_inner(props: &SessionProps) -> Html {
             }
 
             {
-                if *loading {
+                if let Some(reason) = ended_reason.as_ref() {
+                    let detail = match reason {
+                        SessionEndReason::Expired => "This session has reached the end of its scheduled duration.",
+                        SessionEndReason::Abandoned => "This session was closed after everyone left.",
+                        SessionEndReason::Closed => "The facilitator has ended this session.",
+                    };
+                    html! {
+                        <div class="bg-gray-50 dark:bg-gray-900 border-b-2 border-gray-200 dark:border-gray-800 p-4 md:p-6">
+                            <div class="mx-auto max-w-7xl">
+                                <h2 class="text-lg font-medium text-gray-900 dark:text-gray-100 mb-2 leading-none">{ "Session Ended" }</h2>
+                                <p class="text-gray-600 dark:text-gray-400 mb-4">{ detail }</p>
+                                <a
+                                    href="/"
+                                    class="inline-block px-4 py-2 bg-blue-600 hover:bg-blue-700 text-white font-medium rounded-md transition-colors focus:outline-none focus:ring-2 focus:ring-blue-500"
+                                >
+                                    { "Start a new session" }
+                                </a>
+                            </div>
+                        </div>
+                    }
+                } else if *loading {
                     html! {
                         <div class="flex items-center justify-center py-12">
                             <div class="flex items-center space-x-2">
@@ -413,6 +590,7 @@ fn session_page_inner(props: &SessionProps) -> Html {
                                                                 type="text"
                                                                 class="w-full px-3 py-2 border-b border-blue-300 dark:border-blue-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-gray-100 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
                                                                 placeholder="Enter your name..."
+                                                                maxlength={MAX_PARTICIPANT_NAME_CHARS.to_string()}
                                                                 value={(*participant_name).clone()}
                                                                 oninput={{
                                                                     let participant_name = participant_name.clone();
@@ -451,9 +629,12 @@ fn session_page_inner(props: &SessionProps) -> Html {
                                                                 })
                                                             }}
                                                         >
-                                                            { "Join as Participant" }
+                                                            { if requests.requests.is_pending("JoinSession") { "Joining..." } else { "Join as Participant" } }
                                                         </button>
                                                     </div>
+                                                    <div class="mt-2">
+                                                        <RequestStatusNote requests={requests.requests.clone()} actions={&["JoinSession"][..]} />
+                                                    </div>
                                                 </div>
                                             </div>
                                         </div>
@@ -481,11 +662,13 @@ fn session_page_inner(props: &SessionProps) -> Html {
                             <SessionView
                                 session={session.clone()}
                                 on_refresh={refresh_session.clone()}
-                                on_send_message={Some(ws.send.clone())}
+                                on_send_message={Some(requests.send.clone())}
+                                requests={requests.requests.clone()}
                                 participant_name={if *is_joined { Some((*participant_name).clone()) } else { None }}
                                 participant_id={if *is_joined { (*participant_id).clone() } else { None }}
                                 is_joined={*is_joined}
                                 ws_state={ws.state.clone()}
+                                ws_reconnect={ws.reconnect.clone()}
                             />
                         </div>
                     }
@@ -1,5 +1,7 @@
 use kiko::api::{ApiClient, ApiClientHttp, ApiError};
-use kiko::data::{CreateSession, Session};
+use kiko::data::{CreateSession, CreatedSession, RoundResult, Session};
+
+use super::BackendConfig;
 
 /// The main API client for the Kiko application, providing methods to interact with the backend API.
 pub struct Api {
@@ -16,16 +18,22 @@ impl Api {
     pub async fn create_session(
         &self,
         create_session: &CreateSession,
-    ) -> Result<Session, ApiError> {
+    ) -> Result<CreatedSession, ApiError> {
         self.client.post("/session", create_session).await
     }
 
     pub async fn fetch_session(&self, session_id: &str) -> Result<Session, ApiError> {
         self.client.get(&format!("/session/{session_id}")).await
     }
+
+    pub async fn fetch_history(&self, session_id: &str) -> Result<Vec<RoundResult>, ApiError> {
+        self.client
+            .get(&format!("/session/{session_id}/history"))
+            .await
+    }
 }
 
-/// Create a new instance of the API client with the default base URL.
-pub fn create() -> Api {
-    Api::new("http://localhost:3030/api/v1") // or your base URL
+/// Create a new instance of the API client for the configured backend.
+pub fn create(backend: &BackendConfig) -> Api {
+    Api::new(&backend.api_url)
 }
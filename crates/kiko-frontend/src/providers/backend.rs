@@ -0,0 +1,90 @@
+//! Where the backend lives, discovered when the app starts.
+//!
+//! Deployments can serve a `config.json` next to `index.html`:
+//!
+//! ```json
+//! { "api_url": "https://api.kiko.example.com/api/v1" }
+//! ```
+//!
+//! `ws_url` may be given as well, otherwise it is derived from `api_url`. Without a
+//! `config.json` the backend is expected on the same origin as the frontend, which
+//! is how `trunk serve` proxies it during development.
+
+use gloo_net::http::Request;
+use kiko::{log::info, serde::Deserialize};
+use web_sys::window;
+use yew::prelude::*;
+
+/// The file deployments can use to point the frontend at their backend.
+const CONFIG_PATH: &str = "/config.json";
+
+/// Base URLs of the backend's REST API and WebSocket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendConfig {
+    /// Base URL of the REST API, e.g. `https://kiko.example.com/api/v1`
+    pub api_url: String,
+    /// URL of the WebSocket endpoint, e.g. `wss://kiko.example.com/api/v1/ws`
+    pub ws_url: String,
+}
+
+/// The contents of `config.json`.
+#[derive(Deserialize)]
+#[serde(crate = "kiko::serde")]
+struct ConfigFile {
+    api_url: String,
+    ws_url: Option<String>,
+}
+
+impl BackendConfig {
+    /// Builds the config from the API's base URL.
+    ///
+    /// The WebSocket lives at `/ws` below the API, on `wss://` if the API uses HTTPS.
+    pub fn from_api_url(api_url: &str) -> Self {
+        let api_url = api_url.trim_end_matches('/').to_string();
+        let ws_base = match api_url.split_once("://") {
+            Some(("https", rest)) => format!("wss://{rest}"),
+            Some(("http", rest)) => format!("ws://{rest}"),
+            _ => api_url.clone(),
+        };
+
+        Self {
+            ws_url: format!("{ws_base}/ws"),
+            api_url,
+        }
+    }
+
+    /// Expects the backend on the same origin the frontend was loaded from.
+    pub fn same_origin() -> Self {
+        let origin = window()
+            .and_then(|window| window.location().origin().ok())
+            .unwrap_or_default();
+        Self::from_api_url(&format!("{origin}/api/v1"))
+    }
+
+    /// Loads `config.json`, falling back to the same origin if there isn't one.
+    pub async fn load() -> Self {
+        let file = match Request::get(CONFIG_PATH).send().await {
+            Ok(response) if response.ok() => response.json::<ConfigFile>().await.ok(),
+            _ => None,
+        };
+
+        let config = match file {
+            Some(file) => {
+                let mut config = Self::from_api_url(&file.api_url);
+                if let Some(ws_url) = file.ws_url {
+                    config.ws_url = ws_url;
+                }
+                config
+            }
+            None => Self::same_origin(),
+        };
+        info!("🔧 Using backend at {} ({})", config.api_url, config.ws_url);
+        config
+    }
+}
+
+/// Returns the backend config provided at the root of the app.
+#[hook]
+pub fn use_backend() -> BackendConfig {
+    use_context::<BackendConfig>().expect("BackendConfig must be provided at the root of the app")
+}
@@ -1,8 +1,10 @@
 //! Context providers for shared application state and services.
 
 pub mod api;
+pub mod backend;
 pub mod confetti;
 pub mod theme;
 
+pub use backend::{BackendConfig, use_backend};
 pub use confetti::ConfettiProvider;
 pub use theme::{Theme, ThemeProvider, use_theme};
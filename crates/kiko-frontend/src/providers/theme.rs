@@ -69,16 +69,16 @@ pub fn theme_provider(props: &ThemeProviderProps) -> Html {
 
             LocalStorage::set("theme", new_theme.as_str()).ok();
 
-            if let Some(document) = web_sys::window().and_then(|w| w.document()) {
-                if let Some(html) = document.document_element() {
-                    let class_list = html.class_list();
-                    match new_theme {
-                        Theme::Dark => {
-                            class_list.add_1("dark").ok();
-                        }
-                        Theme::Light => {
-                            class_list.remove_1("dark").ok();
-                        }
+            if let Some(document) = web_sys::window().and_then(|w| w.document())
+                && let Some(html) = document.document_element()
+            {
+                let class_list = html.class_list();
+                match new_theme {
+                    Theme::Dark => {
+                        class_list.add_1("dark").ok();
+                    }
+                    Theme::Light => {
+                        class_list.remove_1("dark").ok();
                     }
                 }
             }
@@ -88,16 +88,16 @@ pub fn theme_provider(props: &ThemeProviderProps) -> Html {
     };
 
     use_effect_with(theme.clone(), |theme| {
-        if let Some(document) = web_sys::window().and_then(|w| w.document()) {
-            if let Some(html) = document.document_element() {
-                let class_list = html.class_list();
-                match **theme {
-                    Theme::Dark => {
-                        class_list.add_1("dark").ok();
-                    }
-                    Theme::Light => {
-                        class_list.remove_1("dark").ok();
-                    }
+        if let Some(document) = web_sys::window().and_then(|w| w.document())
+            && let Some(html) = document.document_element()
+        {
+            let class_list = html.class_list();
+            match **theme {
+                Theme::Dark => {
+                    class_list.add_1("dark").ok();
+                }
+                Theme::Light => {
+                    class_list.remove_1("dark").ok();
                 }
             }
         }
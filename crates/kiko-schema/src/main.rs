@@ -0,0 +1,83 @@
+//! Writes the JSON Schema and TypeScript types of the shared `kiko::data` types.
+//!
+//! Run `cargo run -p kiko-schema` after changing a type and commit the files in
+//! `schema/`, the tests fail while they are out of date.
+
+use std::{fs, io, path::PathBuf};
+
+use kiko::schema;
+
+const JSON_SCHEMA_FILE: &str = "kiko.schema.json";
+const TYPESCRIPT_FILE: &str = "kiko.d.ts";
+
+/// The `schema/` directory at the root of the workspace.
+fn schema_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../schema")
+}
+
+fn json_schema() -> String {
+    let mut json = kiko::serde_json::to_string_pretty(&schema::json_schema())
+        .expect("JSON values always serialize");
+    json.push('\n');
+    json
+}
+
+fn main() -> io::Result<()> {
+    let dir = schema_dir();
+    fs::create_dir_all(&dir)?;
+
+    for (file, contents) in [
+        (JSON_SCHEMA_FILE, json_schema()),
+        (TYPESCRIPT_FILE, schema::typescript()),
+    ] {
+        let path = dir.join(file);
+        fs::write(&path, contents)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_up_to_date(file: &str, generated: String) {
+        let checked_in = fs::read_to_string(schema_dir().join(file)).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "schema/{file} is out of date, run `cargo run -p kiko-schema` and commit the result"
+        );
+    }
+
+    #[test]
+    fn json_schema_is_up_to_date() {
+        assert_up_to_date(JSON_SCHEMA_FILE, json_schema());
+    }
+
+    #[test]
+    fn typescript_is_up_to_date() {
+        assert_up_to_date(TYPESCRIPT_FILE, schema::typescript());
+    }
+
+    /// `for_each_type!` is kept by hand, so check no type in `kiko::data` was left out.
+    #[test]
+    fn every_data_type_is_exported() {
+        let data_rs = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../kiko/src/data.rs");
+        let data = fs::read_to_string(data_rs).unwrap();
+        let typescript = schema::typescript();
+
+        let missing: Vec<&str> = data
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("pub struct ")
+                    .or_else(|| line.strip_prefix("pub enum "))
+            })
+            .filter_map(|rest| rest.split(|c: char| !c.is_alphanumeric()).next())
+            .filter(|name| !typescript.contains(&format!("export type {name} ")))
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "{missing:?} missing from `for_each_type!` in crates/kiko/src/schema.rs"
+        );
+    }
+}
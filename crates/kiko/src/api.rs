@@ -21,6 +21,8 @@
 use gloo_net::http::Response;
 use std::collections::HashMap;
 
+use crate::data::{ErrorBody, ErrorCode};
+
 /// HTTP methods supported by the API client.
 #[derive(Debug, Clone, Copy)]
 pub enum HttpMethod {
@@ -33,25 +35,31 @@ pub enum HttpMethod {
 
 /// Comprehensive error types for API operations.
 ///
-/// These errors cover common HTTP status codes as well as network and parsing errors
-/// that can occur during API interactions.
+/// Error responses carry the server's [`ErrorBody`], whose message is shown as is.
+/// Network and parsing errors that occur on the client side are covered as well.
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
     /// Resource not found (404)
-    #[error("Not Found: {0}")]
-    NotFound(String),
-    /// Bad request (400)
-    #[error("Bad Request: {0}")]
-    BadRequest(String),
+    #[error("{}", .0.message)]
+    NotFound(ErrorBody),
+    /// Bad request (400 or 422)
+    #[error("{}", .0.message)]
+    BadRequest(ErrorBody),
     /// Internal server error (5xx)
-    #[error("Internal Server Error")]
-    InternalServerError,
+    #[error("{}", .0.message)]
+    InternalServerError(ErrorBody),
     /// Unauthorized access (401)
-    #[error("Unauthorized Access")]
-    UnauthorizedAccess,
+    #[error("{}", .0.message)]
+    UnauthorizedAccess(ErrorBody),
     /// Forbidden access (403)
-    #[error("Forbidden Access")]
-    ForbiddenAccess,
+    #[error("{}", .0.message)]
+    ForbiddenAccess(ErrorBody),
+    /// Conflicts with the server's state (409)
+    #[error("{}", .0.message)]
+    Conflict(ErrorBody),
+    /// Rate limited (429), with the seconds to wait if the server said so
+    #[error("Too many requests, please try again {}", retry_hint(.0))]
+    TooManyRequests(Option<u64>),
     /// Network-related errors during request
     #[error("Network error: {0}")]
     NetworkError(gloo_net::Error),
@@ -66,6 +74,54 @@ pub enum ApiError {
     UnexpectedStatusCode(u16),
 }
 
+impl ApiError {
+    /// The server's error code, if the server answered with an error.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::NotFound(body)
+            | Self::BadRequest(body)
+            | Self::InternalServerError(body)
+            | Self::UnauthorizedAccess(body)
+            | Self::ForbiddenAccess(body)
+            | Self::Conflict(body) => Some(body.code),
+            Self::TooManyRequests(_) => Some(ErrorCode::RateLimited),
+            _ => None,
+        }
+    }
+}
+
+fn retry_hint(retry_after: &Option<u64>) -> String {
+    match retry_after {
+        Some(seconds) => format!("in {seconds}s"),
+        None => "later".to_string(),
+    }
+}
+
+/// Decodes the [`ErrorBody`] of an error response.
+///
+/// Responses the API handlers didn't produce, like a request body the server
+/// couldn't parse, are plain text. Their text becomes the message instead.
+fn decode_error_body(status: u16, text: &str, endpoint: &str) -> ErrorBody {
+    if let Ok(body) = serde_json::from_str::<ErrorBody>(text) {
+        return body;
+    }
+
+    let code = if status >= 500 {
+        ErrorCode::Internal
+    } else {
+        ErrorCode::InvalidMessage
+    };
+    let message = match text.trim() {
+        "" => format!("Request to {endpoint} failed with status {status}"),
+        text => text.to_string(),
+    };
+    ErrorBody {
+        code,
+        message,
+        violations: Vec::new(),
+    }
+}
+
 /// Result type alias for API operations.
 type ApiResult<T> = Result<T, ApiError>;
 
@@ -113,17 +169,33 @@ impl From<ApiHeaders> for gloo_net::http::Headers {
 /// * `endpoint` - The endpoint that was called (used for error messages)
 ///
 /// # Returns
-/// The original response if successful, or an ApiError for non-success status codes
+/// The original response if successful, or an ApiError carrying the server's
+/// [`ErrorBody`] for non-success status codes
 async fn handle_response_status(response: Response, endpoint: &str) -> ApiResult<Response> {
-    match response.status() {
-        200..=299 => Ok(response),
-        400 => Err(ApiError::BadRequest(format!("Bad request to {endpoint}"))),
-        401 => Err(ApiError::UnauthorizedAccess),
-        403 => Err(ApiError::ForbiddenAccess),
-        404 => Err(ApiError::NotFound(format!("{endpoint} not found"))),
-        500..=599 => Err(ApiError::InternalServerError),
-        status => Err(ApiError::UnexpectedStatusCode(status)),
+    let status = response.status();
+    if (200..=299).contains(&status) {
+        return Ok(response);
+    }
+
+    if status == 429 {
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|seconds| seconds.parse().ok());
+        return Err(ApiError::TooManyRequests(retry_after));
     }
+
+    let text = response.text().await.unwrap_or_default();
+    let body = decode_error_body(status, &text, endpoint);
+    Err(match status {
+        400 | 422 => ApiError::BadRequest(body),
+        401 => ApiError::UnauthorizedAccess(body),
+        403 => ApiError::ForbiddenAccess(body),
+        404 => ApiError::NotFound(body),
+        409 => ApiError::Conflict(body),
+        500..=599 => ApiError::InternalServerError(body),
+        status => ApiError::UnexpectedStatusCode(status),
+    })
 }
 
 /// Parses a JSON response into the specified type.
@@ -426,3 +498,24 @@ impl ApiClient for ApiClientHttp {
         handle_json_response(response, endpoint).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_bodies_keep_the_server_message() {
+        let text = r#"{"code":"session_not_found","message":"Session abc not found"}"#;
+        let body = decode_error_body(404, text, "/session/abc");
+        assert_eq!(body.code, ErrorCode::SessionNotFound);
+        assert_eq!(body.message, "Session abc not found");
+
+        // Plain text rejections keep their text, empty ones say what failed
+        let body = decode_error_body(422, "missing field `name`", "/session");
+        assert_eq!(body.code, ErrorCode::InvalidMessage);
+        assert_eq!(body.message, "missing field `name`");
+        let body = decode_error_body(502, "", "/session");
+        assert_eq!(body.code, ErrorCode::Internal);
+        assert_eq!(body.message, "Request to /session failed with status 502");
+    }
+}
@@ -4,11 +4,14 @@
 //! for managing sessions, participants, and communication between frontend and backend.
 //! All types are serializable and designed to work seamlessly with JSON APIs and WebSocket messaging.
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::id::{ParticipantId, SessionId};
+use crate::id::{FacilitatorToken, ParticipantId, SessionId, StoryId, VoteId};
 use crate::log;
 
 /// Represents a participant in a session.
@@ -16,6 +19,8 @@ use crate::log;
 /// A participant has a unique ID and a display name. Participants can join and leave
 /// sessions dynamically, and their information is synchronized across all clients.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct Participant {
     id: ParticipantId,
     name: String,
@@ -35,20 +40,253 @@ impl Participant {
     }
 }
 
+/// The role a participant holds within a session.
+///
+/// The facilitator runs the meeting: they manage the topic and story backlog,
+/// reveal and clear points, and remove participants. Everyone else can only vote.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub enum Role {
+    Facilitator,
+    Participant,
+}
+
+/// Represents a story waiting to be estimated in a session.
+///
+/// Stories are kept in an ordered backlog on the session. When the facilitator
+/// advances to the next story, it is taken from the front of the backlog and
+/// becomes the current topic.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct Story {
+    id: StoryId,
+    title: String,
+}
+
+impl Story {
+    pub fn new(id: StoryId, title: String) -> Self {
+        Self { id, title }
+    }
+
+    pub fn id(&self) -> &StoryId {
+        &self.id
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+/// The set of cards participants can choose from when estimating.
+///
+/// Sessions use one of the built-in presets or a custom list of cards. Cards are
+/// plain strings, so non-numeric cards such as `"?"`, `"☕"` or `"∞"` are allowed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub enum Deck {
+    /// 0, 1, 2, 3, 5, 8, 13, 21
+    #[default]
+    Fibonacci,
+    /// 0, ½, 1, 2, 3, 5, 8, 13, 20, 40, 100
+    ModifiedFibonacci,
+    /// XS, S, M, L, XL, XXL
+    TShirt,
+    /// 1, 2, 4, 8, 16, 32, 64
+    PowersOfTwo,
+    /// A user-supplied list of cards.
+    Custom(Vec<String>),
+}
+
+impl Deck {
+    /// The cards shared by every preset deck.
+    const SPECIAL_CARDS: [&'static str; 2] = ["?", "☕"];
+
+    /// The built-in presets, in the order they should be offered to users.
+    pub const PRESETS: [Deck; 4] = [
+        Deck::Fibonacci,
+        Deck::ModifiedFibonacci,
+        Deck::TShirt,
+        Deck::PowersOfTwo,
+    ];
+
+    /// Returns a human readable name for the deck.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Deck::Fibonacci => "Fibonacci",
+            Deck::ModifiedFibonacci => "Modified Fibonacci",
+            Deck::TShirt => "T-Shirt Sizes",
+            Deck::PowersOfTwo => "Powers of Two",
+            Deck::Custom(_) => "Custom",
+        }
+    }
+
+    /// Returns the cards in this deck, in display order.
+    pub fn cards(&self) -> Vec<String> {
+        let values: &[&str] = match self {
+            Deck::Fibonacci => &["0", "1", "2", "3", "5", "8", "13", "21"],
+            Deck::ModifiedFibonacci => {
+                &["0", "½", "1", "2", "3", "5", "8", "13", "20", "40", "100"]
+            }
+            Deck::TShirt => &["XS", "S", "M", "L", "XL", "XXL"],
+            Deck::PowersOfTwo => &["1", "2", "4", "8", "16", "32", "64"],
+            Deck::Custom(cards) => return cards.clone(),
+        };
+
+        values
+            .iter()
+            .chain(Self::SPECIAL_CARDS.iter())
+            .map(|card| card.to_string())
+            .collect()
+    }
+
+    /// Returns `true` if the given card belongs to this deck.
+    pub fn contains(&self, card: &str) -> bool {
+        self.cards().iter().any(|c| c == card)
+    }
+}
+
+/// A single participant's vote in a completed round.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct Vote {
+    id: VoteId,
+    participant_id: ParticipantId,
+    participant_name: String,
+    points: Option<String>,
+    /// Set when the vote was never revealed and is redacted for this viewer.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    hidden: bool,
+}
+
+impl Vote {
+    pub fn new(
+        id: VoteId,
+        participant_id: ParticipantId,
+        participant_name: String,
+        points: Option<String>,
+    ) -> Self {
+        Self {
+            id,
+            participant_id,
+            participant_name,
+            points,
+            hidden: false,
+        }
+    }
+
+    pub fn id(&self) -> &VoteId {
+        &self.id
+    }
+
+    pub fn participant_id(&self) -> &ParticipantId {
+        &self.participant_id
+    }
+
+    pub fn participant_name(&self) -> &str {
+        &self.participant_name
+    }
+
+    pub fn points(&self) -> Option<&str> {
+        self.points.as_deref()
+    }
+
+    /// Whether the vote's points were redacted because they were never revealed.
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+}
+
+/// How many completed rounds a session keeps, older ones are dropped.
+pub const MAX_HISTORY_ROUNDS: usize = 100;
+
+/// The recorded outcome of a completed estimation round.
+///
+/// A round is archived into the session history whenever the points are
+/// cleared, so the team can look back at what was agreed on. Timestamps are
+/// seconds since the Unix epoch.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct RoundResult {
+    topic: String,
+    votes: Vec<Vote>,
+    // Timestamps are far below 2^53, so TypeScript can treat them as plain numbers
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
+    started: u64,
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
+    ended: u64,
+    revealed: bool,
+}
+
+impl RoundResult {
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn votes(&self) -> &Vec<Vote> {
+        &self.votes
+    }
+
+    pub fn started(&self) -> u64 {
+        self.started
+    }
+
+    pub fn ended(&self) -> u64 {
+        self.ended
+    }
+
+    pub fn revealed(&self) -> bool {
+        self.revealed
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct Session {
     pub id: SessionId,
     name: String,
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
     started: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = crate::openapi::DurationSchema))]
+    #[cfg_attr(feature = "schema", ts(as = "crate::schema::DurationSchema"))]
     duration: Duration,
     members: Vec<Participant>,
+    facilitator: Option<ParticipantId>,
+    /// The secret that grants the facilitator role, see [`Session::claim_facilitator`].
+    ///
+    /// Only handed to the session's creator, [`Session::redacted_for`] strips it from every copy sent out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(ignore))]
+    #[cfg_attr(feature = "schema", schemars(skip), ts(skip))]
+    facilitator_token: Option<FacilitatorToken>,
     current_topic: String,
-    current_points: HashMap<ParticipantId, Option<u32>>,
+    current_points: HashMap<ParticipantId, Option<String>>,
     hide_points: bool,
+    deck: Deck,
+    stories: Vec<Story>,
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
+    round_started: u64,
+    /// Counts the changes stored so far, so an older snapshot can be told from a newer one.
+    #[serde(default)]
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
+    version: u64,
+    /// Completed rounds, only kept in storage and served by themselves, see [`Session::history_for`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<RoundResult>,
+    /// Participants whose hidden votes were redacted from this copy of the session.
+    ///
+    /// Only ever filled in by [`Session::redacted_for`], the stored session keeps every vote.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    hidden_votes: HashSet<ParticipantId>,
 }
 
 impl Session {
-    pub fn new(name: String, duration: Duration) -> Self {
+    pub fn new(name: String, duration: Duration, deck: Deck) -> Self {
         let started = std::time::UNIX_EPOCH.elapsed().unwrap().as_secs();
         let id = SessionId::new();
 
@@ -58,18 +296,83 @@ impl Session {
             started,
             duration,
             members: Vec::new(),
+            facilitator: None,
+            facilitator_token: Some(FacilitatorToken::new()),
             current_topic: String::new(),
             current_points: HashMap::new(),
             hide_points: true,
+            deck,
+            stories: Vec::new(),
+            round_started: started,
+            version: 0,
+            history: Vec::new(),
+            hidden_votes: HashSet::new(),
         }
     }
 
+    /// Adds a participant to the session.
+    ///
+    /// Joining never makes anyone the facilitator, see [`Session::claim_facilitator`].
     pub fn add_participant(&mut self, participant: Participant) {
         self.members.push(participant);
     }
 
+    /// Removes a participant from the session.
+    ///
+    /// If the facilitator leaves, the role stays vacant until the token holder
+    /// claims it again.
     pub fn remove_participant(&mut self, participant_id: &ParticipantId) {
         self.members.retain(|p| &p.id != participant_id);
+
+        if self.facilitator.as_ref() == Some(participant_id) {
+            self.facilitator = None;
+        }
+    }
+
+    /// Makes a participant the facilitator if they present the session's facilitator token.
+    ///
+    /// Returns whether the role was granted, it stays where it was otherwise.
+    pub fn claim_facilitator(
+        &mut self,
+        participant_id: &ParticipantId,
+        token: &FacilitatorToken,
+    ) -> bool {
+        let is_member = self.members.iter().any(|p| &p.id == participant_id);
+        if !is_member || self.facilitator_token.as_ref() != Some(token) {
+            return false;
+        }
+        self.facilitator = Some(participant_id.clone());
+        true
+    }
+
+    /// The secret that grants the facilitator role, `None` on redacted copies.
+    pub fn facilitator_token(&self) -> Option<&FacilitatorToken> {
+        self.facilitator_token.as_ref()
+    }
+
+    /// Returns the role of a participant, or `None` if they are not in the session.
+    pub fn role(&self, participant_id: &ParticipantId) -> Option<Role> {
+        if !self.members.iter().any(|p| &p.id == participant_id) {
+            return None;
+        }
+        if self.facilitator.as_ref() == Some(participant_id) {
+            Some(Role::Facilitator)
+        } else {
+            Some(Role::Participant)
+        }
+    }
+
+    pub fn is_facilitator(&self, participant_id: &ParticipantId) -> bool {
+        self.role(participant_id) == Some(Role::Facilitator)
+    }
+
+    pub fn facilitator(&self) -> Option<&ParticipantId> {
+        self.facilitator.as_ref()
+    }
+
+    /// Whether the session has reached [`MAX_PARTICIPANTS`].
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTICIPANTS
     }
 
     pub fn is_active(&self) -> bool {
@@ -90,8 +393,96 @@ impl Session {
         self.current_topic = topic;
     }
 
+    /// Clears the points for the current round.
+    ///
+    /// If anyone voted, the round is first archived into the session history
+    /// along with the topic and whether the votes were revealed.
     pub fn clear_points(&mut self) {
+        let now = std::time::UNIX_EPOCH.elapsed().unwrap().as_secs();
+
+        if !self.current_points.is_empty() {
+            let votes = self
+                .members
+                .iter()
+                .filter_map(|member| {
+                    let points = self.current_points.get(&member.id)?;
+                    Some(Vote::new(
+                        VoteId::new(),
+                        member.id.clone(),
+                        member.name.clone(),
+                        points.clone(),
+                    ))
+                })
+                .collect();
+
+            self.history.push(RoundResult {
+                topic: self.current_topic.clone(),
+                votes,
+                started: self.round_started,
+                ended: now,
+                revealed: !self.hide_points,
+            });
+
+            let excess = self.history.len().saturating_sub(MAX_HISTORY_ROUNDS);
+            self.history.drain(..excess);
+        }
+
         self.current_points.clear();
+        self.hidden_votes.clear();
+        self.round_started = now;
+    }
+
+    /// Returns a copy of the session as `viewer` is allowed to see it.
+    ///
+    /// While points are hidden, everyone else's vote in the current round is
+    /// replaced by a "has voted" marker, see [`Session::has_voted`]. Observers who
+    /// haven't joined pass `None` and see no votes at all.
+    ///
+    /// The round history is left out, it is fetched separately with [`Session::history_for`]
+    /// so it isn't sent along with every update. The facilitator token is never included.
+    pub fn redacted_for(&self, viewer: Option<&ParticipantId>) -> Session {
+        let mut session = self.clone();
+        session.history.clear();
+        session.facilitator_token = None;
+        let is_viewer = |id: &ParticipantId| viewer == Some(id);
+
+        if session.hide_points {
+            let hidden: Vec<ParticipantId> = session
+                .current_points
+                .keys()
+                .filter(|id| !is_viewer(id))
+                .cloned()
+                .collect();
+            for id in hidden {
+                session.current_points.remove(&id);
+                session.hidden_votes.insert(id);
+            }
+        }
+
+        session
+    }
+
+    /// Returns the completed rounds, oldest first, as `viewer` is allowed to see them.
+    ///
+    /// Votes from rounds that were archived without being revealed are redacted,
+    /// except for the viewer's own.
+    pub fn history_for(&self, viewer: Option<&ParticipantId>) -> Vec<RoundResult> {
+        let mut history = self.history.clone();
+        for round in history.iter_mut().filter(|r| !r.revealed) {
+            for vote in round.votes.iter_mut() {
+                if viewer != Some(&vote.participant_id) {
+                    vote.points = None;
+                    vote.hidden = true;
+                }
+            }
+        }
+        history
+    }
+
+    /// Whether the participant has voted in the current round, even if their vote is hidden.
+    pub fn has_voted(&self, participant_id: &ParticipantId) -> bool {
+        self.current_points.contains_key(participant_id)
+            || self.hidden_votes.contains(participant_id)
     }
 
     pub fn hide_points(&self) -> bool {
@@ -102,7 +493,11 @@ impl Session {
         self.hide_points = !self.hide_points;
     }
 
-    pub fn point(&mut self, participant_id: &ParticipantId, points: Option<u32>) {
+    pub fn reveal_points(&mut self) {
+        self.hide_points = false;
+    }
+
+    pub fn point(&mut self, participant_id: &ParticipantId, points: Option<String>) {
         // Validate participant exists
         if !self.members.iter().any(|p| &p.id == participant_id) {
             log::warn!(
@@ -116,6 +511,79 @@ impl Session {
         self.current_points.insert(participant_id.clone(), points);
     }
 
+    pub fn add_story(&mut self, story: Story) {
+        self.stories.push(story);
+    }
+
+    pub fn remove_story(&mut self, story_id: &StoryId) {
+        self.stories.retain(|s| &s.id != story_id);
+    }
+
+    /// Moves a story to a new position in the backlog.
+    ///
+    /// Positions past the end of the backlog move the story to the back.
+    /// Unknown story IDs are ignored.
+    pub fn move_story(&mut self, story_id: &StoryId, position: usize) {
+        let Some(index) = self.stories.iter().position(|s| &s.id == story_id) else {
+            log::warn!("Story ID {:?} not found in session {:?}", story_id, self.id);
+            return;
+        };
+        let story = self.stories.remove(index);
+        let position = position.min(self.stories.len());
+        self.stories.insert(position, story);
+    }
+
+    /// Advances to the next story in the backlog.
+    ///
+    /// The story at the front of the backlog becomes the current topic and the
+    /// points from the previous round are cleared. Returns the story that was
+    /// started, or `None` if the backlog is empty.
+    pub fn next_story(&mut self) -> Option<Story> {
+        if self.stories.is_empty() {
+            return None;
+        }
+        let story = self.stories.remove(0);
+        self.set_topic(story.title.clone());
+        self.clear_points();
+        Some(story)
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    pub fn history(&self) -> &Vec<RoundResult> {
+        &self.history
+    }
+
+    /// When the current round started, it changes whenever a round is archived.
+    pub fn round_started(&self) -> u64 {
+        self.round_started
+    }
+
+    /// Goes up with every stored change, see [`Session::next_version`].
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Marks the session as changed, called by the storage whenever it stores a change.
+    pub fn next_version(&mut self) {
+        self.version += 1;
+    }
+
+    /// The listing entry for this session, safe to show to anyone.
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            participant_count: self.members.len(),
+        }
+    }
+
+    pub fn stories(&self) -> &Vec<Story> {
+        &self.stories
+    }
+
     pub fn participants(&self) -> &Vec<Participant> {
         &self.members
     }
@@ -124,7 +592,7 @@ impl Session {
         &self.current_topic
     }
 
-    pub fn current_points(&self) -> &HashMap<ParticipantId, Option<u32>> {
+    pub fn current_points(&self) -> &HashMap<ParticipantId, Option<String>> {
         &self.current_points
     }
 
@@ -142,38 +610,331 @@ impl Session {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct CreateSession {
     pub name: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = crate::openapi::DurationSchema))]
+    #[cfg_attr(feature = "schema", ts(as = "crate::schema::DurationSchema"))]
     pub duration: Duration,
+    #[serde(default)]
+    pub deck: Deck,
+}
+
+impl CreateSession {
+    /// Checks the request against the validation rules, returning it with its name trimmed.
+    ///
+    /// Every violation is reported, not just the first one.
+    pub fn validate(self) -> Result<Self, Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let name = validate_session_name(&self.name).unwrap_or_else(|error| {
+            errors.push(error);
+            self.name
+        });
+        if let Err(error) = validate_duration(self.duration) {
+            errors.push(error);
+        }
+        let deck = validate_deck(self.deck).unwrap_or_else(|error| {
+            errors.push(error);
+            Deck::default()
+        });
+
+        if errors.is_empty() {
+            Ok(Self { name, deck, ..self })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Longest session name accepted, in characters.
+pub const MAX_SESSION_NAME_CHARS: usize = 80;
+/// Longest participant name accepted, in characters.
+pub const MAX_PARTICIPANT_NAME_CHARS: usize = 40;
+/// Longest topic or story title accepted, in characters.
+///
+/// Stories become the topic when they come up, so both share a limit.
+pub const MAX_TOPIC_CHARS: usize = 200;
+/// Longest a session can be scheduled for.
+pub const MAX_SESSION_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Most participants a single session can have.
+pub const MAX_PARTICIPANTS: usize = 50;
+/// Most cards a custom deck can have.
+pub const MAX_DECK_CARDS: usize = 20;
+/// Longest card accepted in a custom deck, in characters.
+pub const MAX_CARD_CHARS: usize = 12;
+
+/// A user-provided field checked by the validation rules.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    SessionName,
+    ParticipantName,
+    Topic,
+    StoryTitle,
+    Duration,
+    Deck,
+    Card,
+    Participants,
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::SessionName => "Session name",
+            Self::ParticipantName => "Name",
+            Self::Topic => "Topic",
+            Self::StoryTitle => "Story title",
+            Self::Duration => "Duration",
+            Self::Deck => "Deck",
+            Self::Card => "Card",
+            Self::Participants => "Participants",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A broken validation rule, shared by the backend and the frontend.
+///
+/// The backend enforces these rules, the frontend checks them before sending so
+/// users see problems right away.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ValidationError {
+    #[error("{field} can't be empty")]
+    Empty { field: Field },
+    #[error("{field} can't be longer than {max_chars} characters")]
+    TooLong { field: Field, max_chars: usize },
+    /// Line breaks and other control characters aren't allowed in names and topics.
+    #[error("{field} can't contain line breaks or control characters")]
+    InvalidCharacters { field: Field },
+    #[error("Sessions must last at least a minute and at most {} hours", .max_secs / 3600)]
+    InvalidDuration {
+        #[cfg_attr(feature = "schema", ts(type = "number"))]
+        max_secs: u64,
+    },
+    #[error("Custom decks need at least one card and no duplicates")]
+    InvalidDeck,
+    #[error("Custom decks can't have more than {max} cards")]
+    TooManyCards { max: usize },
+    #[error("Sessions can't have more than {max} participants")]
+    SessionFull { max: usize },
+}
+
+impl ValidationError {
+    /// The field that broke the rule.
+    pub fn field(&self) -> Field {
+        match self {
+            Self::Empty { field }
+            | Self::TooLong { field, .. }
+            | Self::InvalidCharacters { field } => *field,
+            Self::InvalidDuration { .. } => Field::Duration,
+            Self::InvalidDeck | Self::TooManyCards { .. } => Field::Deck,
+            Self::SessionFull { .. } => Field::Participants,
+        }
+    }
+}
+
+/// Trims a single line of user text and checks it against a field's rules.
+fn validate_text(
+    field: Field,
+    text: &str,
+    max_chars: usize,
+    required: bool,
+) -> Result<String, ValidationError> {
+    let text = text.trim();
+
+    if required && text.is_empty() {
+        return Err(ValidationError::Empty { field });
+    }
+    if text.chars().count() > max_chars {
+        return Err(ValidationError::TooLong { field, max_chars });
+    }
+    if text.chars().any(char::is_control) {
+        return Err(ValidationError::InvalidCharacters { field });
+    }
+
+    Ok(text.to_string())
+}
+
+/// Checks a session name, returning it trimmed.
+pub fn validate_session_name(name: &str) -> Result<String, ValidationError> {
+    validate_text(Field::SessionName, name, MAX_SESSION_NAME_CHARS, true)
+}
+
+/// Checks a participant's name, returning it trimmed.
+pub fn validate_participant_name(name: &str) -> Result<String, ValidationError> {
+    validate_text(
+        Field::ParticipantName,
+        name,
+        MAX_PARTICIPANT_NAME_CHARS,
+        true,
+    )
+}
+
+/// Checks a topic, returning it trimmed. An empty topic clears it.
+pub fn validate_topic(topic: &str) -> Result<String, ValidationError> {
+    validate_text(Field::Topic, topic, MAX_TOPIC_CHARS, false)
+}
+
+/// Checks a story's title, returning it trimmed.
+pub fn validate_story_title(title: &str) -> Result<String, ValidationError> {
+    validate_text(Field::StoryTitle, title, MAX_TOPIC_CHARS, true)
+}
+
+/// Checks a deck, returning a custom deck's cards trimmed.
+///
+/// Presets are always valid. Custom decks need between one and [`MAX_DECK_CARDS`]
+/// unique cards, each at most [`MAX_CARD_CHARS`] characters long.
+pub fn validate_deck(deck: Deck) -> Result<Deck, ValidationError> {
+    let Deck::Custom(cards) = deck else {
+        return Ok(deck);
+    };
+    if cards.len() > MAX_DECK_CARDS {
+        return Err(ValidationError::TooManyCards {
+            max: MAX_DECK_CARDS,
+        });
+    }
+
+    let cards = cards
+        .iter()
+        .map(|card| validate_text(Field::Card, card, MAX_CARD_CHARS, true))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut seen = HashSet::new();
+    if cards.is_empty() || !cards.iter().all(|card| seen.insert(card.as_str())) {
+        return Err(ValidationError::InvalidDeck);
+    }
+
+    Ok(Deck::Custom(cards))
+}
+
+/// Checks that a session lasts at least a minute and at most [`MAX_SESSION_DURATION`].
+pub fn validate_duration(duration: Duration) -> Result<Duration, ValidationError> {
+    if duration < Duration::from_secs(60) || duration > MAX_SESSION_DURATION {
+        return Err(ValidationError::InvalidDuration {
+            max_secs: MAX_SESSION_DURATION.as_secs(),
+        });
+    }
+    Ok(duration)
 }
 
+/// Answer to creating a session over the REST API.
+///
+/// The facilitator token is a secret, whoever joins with it becomes the facilitator.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct CreatedSession {
+    pub session: Session,
+    pub facilitator_token: String,
+}
+
+/// What anyone may see of a session when listing them.
+///
+/// Leaves out the participants, votes and every secret, see [`Session::summary`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct SessionSummary {
+    pub id: SessionId,
+    pub name: String,
+    pub participant_count: usize,
+}
+
+/// Joins a session as a new participant.
+///
+/// Pass the token from [`CreatedSession`] to join as the facilitator.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct JoinSession {
     pub session_id: String,
     pub participant_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facilitator_token: Option<String>,
 }
 
+/// Sent only to the socket that joined, confirming who it is in the session.
+///
+/// The resume token is a secret, keep it to reclaim this identity later.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct Joined {
+    pub participant_id: String,
+    pub resume_token: String,
+}
+
+/// Reclaims a participant after reconnecting, using the token from [`Joined`].
+///
+/// Also subscribes the connection to the session, and is answered with [`Joined`] again.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct ResumeSession {
+    pub session_id: String,
+    pub resume_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct SubscribeToSession {
     pub session_id: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct AddParticipant {
     pub session_id: String,
     pub participant_name: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct RemoveParticipant {
     pub session_id: String,
     pub participant_id: String,
 }
 
+/// Request body for joining a session over the REST API.
+///
+/// Answered with [`Joined`], whose resume token authenticates later requests
+/// as this participant in an `Authorization: Bearer` header.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct JoinRequest {
+    pub participant_name: String,
+    /// The token from [`CreatedSession`], to join as the facilitator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facilitator_token: Option<String>,
+}
+
+/// Request body for setting a session's topic over the REST API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct TopicRequest {
+    pub topic: String,
+}
+
+/// Request body for voting over the REST API, `None` withdraws the vote.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct VoteRequest {
+    pub points: Option<String>,
+}
+
 /// Health status enumeration.
 ///
 /// Represents the overall health state of the server.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 #[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     Healthy,
@@ -186,6 +947,8 @@ pub enum HealthStatus {
 /// Contains server health information including status, uptime, and service states.
 /// Used by the `/health` endpoint to provide structured health check data.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct HealthResponse {
     pub status: HealthStatus,
     pub timestamp: String,
@@ -196,13 +959,18 @@ pub struct HealthResponse {
 
 /// Uptime information in both seconds and human-readable format.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct UptimeInfo {
+    #[cfg_attr(feature = "schema", ts(type = "number"))]
     pub seconds: i64,
     pub human: String,
 }
 
 /// Service status information.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct ServiceInfo {
     pub sessions: String,
     pub active_sessions: usize,
@@ -210,10 +978,31 @@ pub struct ServiceInfo {
 
 /// Point a given task in the session.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub struct PointSession {
     pub session_id: String,
     pub participant_id: String,
-    pub points: Option<u32>,
+    pub points: Option<String>,
+}
+
+/// Move a story to a new position in the session's backlog.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct ReorderStory {
+    pub story_id: String,
+    pub position: usize,
+}
+
+/// Why a session was ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub enum SessionEndReason {
+    /// The session ran past its duration.
+    Expired,
+    /// The session had no participants for too long.
+    Abandoned,
+    /// The facilitator ended the session.
+    Closed,
 }
 
 /// WebSocket message types for session operations.
@@ -221,15 +1010,576 @@ pub struct PointSession {
 /// This enum defines all possible messages that can be sent between clients and the server
 /// for session management operations. Messages are JSON-serialized for WebSocket transport.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
 pub enum SessionMessage {
     CreateSession(CreateSession),
     JoinSession(JoinSession),
+    Joined(Joined),
+    ResumeSession(ResumeSession),
     SubscribeToSession(SubscribeToSession),
     AddParticipant(AddParticipant),
     RemoveParticipant(RemoveParticipant),
     PointSession(PointSession),
     SetTopic(String),
     ClearPoints,
-    SessionUpdate(Session),
+    SessionUpdate(Box<Session>),
     ToggleHidePoints,
+    AddStory(String),
+    RemoveStory(String),
+    ReorderStory(ReorderStory),
+    NextStory,
+    SessionEnded(SessionEndReason),
+}
+
+impl SessionMessage {
+    /// The name of the variant, e.g. for logging or keying per-action state.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::CreateSession(_) => "CreateSession",
+            Self::JoinSession(_) => "JoinSession",
+            Self::Joined(_) => "Joined",
+            Self::ResumeSession(_) => "ResumeSession",
+            Self::SubscribeToSession(_) => "SubscribeToSession",
+            Self::AddParticipant(_) => "AddParticipant",
+            Self::RemoveParticipant(_) => "RemoveParticipant",
+            Self::PointSession(_) => "PointSession",
+            Self::SetTopic(_) => "SetTopic",
+            Self::ClearPoints => "ClearPoints",
+            Self::SessionUpdate(_) => "SessionUpdate",
+            Self::ToggleHidePoints => "ToggleHidePoints",
+            Self::AddStory(_) => "AddStory",
+            Self::RemoveStory(_) => "RemoveStory",
+            Self::ReorderStory(_) => "ReorderStory",
+            Self::NextStory => "NextStory",
+            Self::SessionEnded(_) => "SessionEnded",
+        }
+    }
+}
+
+/// The WebSocket protocol version spoken by this build.
+///
+/// Bump it whenever a message changes in a way older clients can't parse, and
+/// raise [`MIN_PROTOCOL_VERSION`] once the server stops understanding old clients.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest protocol version the server still accepts.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// The first message a client sends on a new WebSocket connection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct Hello {
+    pub protocol_version: u32,
+    /// Identifies the client in the server logs, e.g. `kiko-frontend/0.1.0`
+    pub client_name: String,
+}
+
+/// The server's answer to an accepted [`Hello`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct Welcome {
+    /// The protocol version the server speaks
+    pub protocol_version: u32,
+    /// The oldest protocol version the server accepts
+    pub min_protocol_version: u32,
+}
+
+/// Everything a client sends to the server over the WebSocket.
+///
+/// Bare session messages are still accepted. Wrapping one in a `Request` makes the
+/// server answer with a [`ServerMessage::Ack`] or [`ServerMessage::Error`] carrying
+/// the same request ID, so the client can tell how that particular message fared.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub enum ClientMessage {
+    Hello(Hello),
+    Request {
+        request_id: String,
+        message: SessionMessage,
+    },
+    #[serde(untagged)]
+    Session(SessionMessage),
+}
+
+impl ClientMessage {
+    /// Reads just the request ID of a `Request`, skipping over the message it wraps.
+    ///
+    /// Lets the server name a request it drops unparsed, e.g. over the rate limit or malformed.
+    pub fn peek_request_id(text: &str) -> Option<String> {
+        #[derive(Deserialize)]
+        enum Peek {
+            Request { request_id: String },
+        }
+
+        match serde_json::from_str(text).ok()? {
+            Peek::Request { request_id } => Some(request_id),
+        }
+    }
+}
+
+/// Stable, machine-readable reason a WebSocket or REST request failed.
+///
+/// Clients match on these instead of the human-readable message, so a code must
+/// never change its meaning once released.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    SessionNotFound,
+    InvalidMessage,
+    AlreadySubscribed,
+    NotSubscribed,
+    InvalidCard,
+    NotFacilitator,
+    NotJoined,
+    NotYourself,
+    ResumeRejected,
+    /// The client speaks a protocol version the server doesn't, it needs to be reloaded.
+    IncompatibleProtocol,
+    /// The client sent too many messages, it should slow down.
+    RateLimited,
+    /// The request was malformed, e.g. a session without a name.
+    ValidationFailed,
+    /// The request clashes with the server's current state.
+    Conflict,
+    Internal,
+}
+
+/// The JSON body of every REST API error response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Every rule the request broke, for [`ErrorCode::ValidationFailed`] errors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<ValidationError>,
+}
+
+/// Everything the server sends to a client over the WebSocket.
+///
+/// Session messages are sent unwrapped, exactly as a bare [`SessionMessage`] would be,
+/// so only errors carry an envelope of their own.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema, ts_rs::TS))]
+pub enum ServerMessage {
+    Welcome(Welcome),
+    /// The request with this ID was handled successfully.
+    Ack {
+        request_id: String,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// The client's ID for the request that failed, if it sent one.
+        request_id: Option<String>,
+    },
+    #[serde(untagged)]
+    Session(SessionMessage),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_with_votes() -> (Session, ParticipantId, ParticipantId) {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+        session.add_participant(Participant::new(alex.clone(), "Alex".to_string()));
+        session.add_participant(Participant::new(sam.clone(), "Sam".to_string()));
+        session.point(&alex, Some("3".to_string()));
+        session.point(&sam, Some("8".to_string()));
+        (session, alex, sam)
+    }
+
+    #[test]
+    fn validation_trims_and_reports_every_violation() {
+        assert_eq!(validate_participant_name("  Alex \t").unwrap(), "Alex");
+        assert_eq!(validate_topic("   ").unwrap(), "");
+        assert_eq!(
+            validate_story_title(" "),
+            Err(ValidationError::Empty {
+                field: Field::StoryTitle
+            })
+        );
+        assert_eq!(
+            validate_participant_name(&"x".repeat(MAX_PARTICIPANT_NAME_CHARS + 1)),
+            Err(ValidationError::TooLong {
+                field: Field::ParticipantName,
+                max_chars: MAX_PARTICIPANT_NAME_CHARS
+            })
+        );
+        // Limits count characters, not bytes
+        assert!(validate_participant_name(&"é".repeat(MAX_PARTICIPANT_NAME_CHARS)).is_ok());
+        assert_eq!(
+            validate_topic("Login\npage"),
+            Err(ValidationError::InvalidCharacters {
+                field: Field::Topic
+            })
+        );
+
+        let request = CreateSession {
+            name: " Planning ".to_string(),
+            duration: Duration::from_secs(3600),
+            deck: Deck::default(),
+        };
+        assert_eq!(request.validate().unwrap().name, "Planning");
+
+        let errors = CreateSession {
+            name: String::new(),
+            duration: MAX_SESSION_DURATION + Duration::from_secs(1),
+            deck: Deck::Custom(vec!["1".to_string(), "1".to_string()]),
+        }
+        .validate()
+        .unwrap_err();
+        let fields: Vec<Field> = errors.iter().map(ValidationError::field).collect();
+        assert_eq!(
+            fields,
+            vec![Field::SessionName, Field::Duration, Field::Deck]
+        );
+    }
+
+    #[test]
+    fn redaction_hides_other_votes_until_reveal() {
+        let (session, alex, sam) = session_with_votes();
+        assert!(session.hide_points());
+
+        let view = session.redacted_for(Some(&alex));
+        assert_eq!(
+            view.current_points().get(&alex),
+            Some(&Some("3".to_string()))
+        );
+        assert!(!view.current_points().contains_key(&sam));
+        assert!(view.has_voted(&sam));
+
+        // The marker survives the trip over the wire, the vote doesn't
+        let json = serde_json::to_string(&view).unwrap();
+        assert!(!json.contains("\"8\""));
+        let decoded: Session = serde_json::from_str(&json).unwrap();
+        assert!(decoded.has_voted(&sam));
+        assert!(decoded.current_points().get(&sam).is_none());
+
+        let observer = session.redacted_for(None);
+        assert!(observer.current_points().is_empty());
+        assert!(observer.has_voted(&alex) && observer.has_voted(&sam));
+    }
+
+    #[test]
+    fn redaction_keeps_revealed_votes() {
+        let (mut session, alex, sam) = session_with_votes();
+        session.toggle_hide_points();
+
+        let view = session.redacted_for(None);
+        assert_eq!(
+            view.current_points().get(&alex),
+            Some(&Some("3".to_string()))
+        );
+        assert_eq!(
+            view.current_points().get(&sam),
+            Some(&Some("8".to_string()))
+        );
+    }
+
+    #[test]
+    fn redaction_hides_unrevealed_history() {
+        let (mut session, alex, _) = session_with_votes();
+        session.clear_points();
+
+        assert!(session.redacted_for(Some(&alex)).history().is_empty());
+
+        let history = session.history_for(Some(&alex));
+        for vote in history[0].votes() {
+            if vote.participant_id() == &alex {
+                assert_eq!(vote.points(), Some("3"));
+                assert!(!vote.is_hidden());
+            } else {
+                assert_eq!(vote.points(), None);
+                assert!(vote.is_hidden());
+            }
+        }
+    }
+
+    fn session_with_stories(titles: &[&str]) -> (Session, Vec<StoryId>) {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        let ids = titles
+            .iter()
+            .map(|title| {
+                let story = Story::new(StoryId::new(), title.to_string());
+                let id = story.id().clone();
+                session.add_story(story);
+                id
+            })
+            .collect();
+        (session, ids)
+    }
+
+    fn titles(session: &Session) -> Vec<&str> {
+        session.stories().iter().map(|s| s.title()).collect()
+    }
+
+    #[test]
+    fn add_and_remove_story() {
+        let (mut session, ids) = session_with_stories(&["Login", "Logout"]);
+        assert_eq!(titles(&session), vec!["Login", "Logout"]);
+
+        session.remove_story(&ids[0]);
+        assert_eq!(titles(&session), vec!["Logout"]);
+    }
+
+    #[test]
+    fn move_story_reorders_backlog() {
+        let (mut session, ids) = session_with_stories(&["A", "B", "C"]);
+
+        session.move_story(&ids[2], 0);
+        assert_eq!(titles(&session), vec!["C", "A", "B"]);
+
+        // Out of range positions move the story to the back
+        session.move_story(&ids[2], 99);
+        assert_eq!(titles(&session), vec!["A", "B", "C"]);
+
+        // Unknown IDs are ignored
+        session.move_story(&StoryId::new(), 0);
+        assert_eq!(titles(&session), vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn next_story_sets_topic_and_clears_points() {
+        let (mut session, _) = session_with_stories(&["First", "Second"]);
+        let participant_id = ParticipantId::new();
+        session.add_participant(Participant::new(participant_id.clone(), "Alex".to_string()));
+        session.point(&participant_id, Some("5".to_string()));
+
+        let story = session.next_story().unwrap();
+        assert_eq!(story.title(), "First");
+        assert_eq!(session.current_topic(), "First");
+        assert!(session.current_points().is_empty());
+        assert_eq!(titles(&session), vec!["Second"]);
+
+        session.next_story();
+        assert!(session.next_story().is_none());
+        assert_eq!(session.current_topic(), "Second");
+    }
+
+    #[test]
+    fn clear_points_records_round_history() {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+        session.add_participant(Participant::new(alex.clone(), "Alex".to_string()));
+        session.add_participant(Participant::new(sam.clone(), "Sam".to_string()));
+        session.set_topic("Checkout flow".to_string());
+        session.point(&alex, Some("3".to_string()));
+        session.point(&sam, Some("5".to_string()));
+        session.toggle_hide_points();
+
+        session.clear_points();
+
+        assert!(session.current_points().is_empty());
+        assert_eq!(session.history().len(), 1);
+
+        let round = &session.history()[0];
+        assert_eq!(round.topic(), "Checkout flow");
+        assert!(round.revealed());
+        assert!(round.started() <= round.ended());
+
+        let votes: Vec<_> = round
+            .votes()
+            .iter()
+            .map(|v| (v.participant_name(), v.points()))
+            .collect();
+        assert_eq!(votes, vec![("Alex", Some("3")), ("Sam", Some("5"))]);
+        assert_ne!(round.votes()[0].id(), round.votes()[1].id());
+    }
+
+    #[test]
+    fn history_keeps_only_the_latest_rounds() {
+        let (mut session, alex, _) = session_with_votes();
+        for round in 0..MAX_HISTORY_ROUNDS + 5 {
+            session.set_topic(format!("Round {round}"));
+            session.point(&alex, Some("1".to_string()));
+            session.clear_points();
+        }
+
+        assert_eq!(session.history().len(), MAX_HISTORY_ROUNDS);
+        assert_eq!(
+            session.history().last().unwrap().topic(),
+            format!("Round {}", MAX_HISTORY_ROUNDS + 4)
+        );
+    }
+
+    #[test]
+    fn only_the_token_holder_becomes_facilitator() {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        let token = session.facilitator_token().unwrap().clone();
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+        session.add_participant(Participant::new(alex.clone(), "Alex".to_string()));
+        session.add_participant(Participant::new(sam.clone(), "Sam".to_string()));
+
+        // Joining first doesn't make anyone the facilitator
+        assert_eq!(session.role(&alex), Some(Role::Participant));
+        assert!(!session.claim_facilitator(&alex, &FacilitatorToken::new()));
+        assert!(!session.claim_facilitator(&ParticipantId::new(), &token));
+        assert!(session.facilitator().is_none());
+
+        assert!(session.claim_facilitator(&sam, &token));
+        assert_eq!(session.role(&sam), Some(Role::Facilitator));
+        assert_eq!(session.role(&alex), Some(Role::Participant));
+        assert_eq!(session.role(&ParticipantId::new()), None);
+        assert!(
+            session
+                .redacted_for(Some(&sam))
+                .facilitator_token()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn facilitator_role_is_vacant_after_leaving() {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        let token = session.facilitator_token().unwrap().clone();
+        let alex = ParticipantId::new();
+        let sam = ParticipantId::new();
+        session.add_participant(Participant::new(alex.clone(), "Alex".to_string()));
+        session.add_participant(Participant::new(sam.clone(), "Sam".to_string()));
+        assert!(session.claim_facilitator(&alex, &token));
+
+        session.remove_participant(&alex);
+        assert!(session.facilitator().is_none());
+        assert!(!session.is_facilitator(&sam));
+    }
+
+    #[test]
+    fn preset_decks_include_special_cards() {
+        for deck in Deck::PRESETS {
+            assert_eq!(validate_deck(deck.clone()), Ok(deck.clone()));
+            assert!(deck.contains("?"));
+            assert!(deck.contains("☕"));
+        }
+        assert!(Deck::TShirt.contains("XL"));
+        assert!(!Deck::Fibonacci.contains("4"));
+        assert!(Deck::Fibonacci.contains("0"));
+    }
+
+    #[test]
+    fn custom_deck_validation() {
+        let deck = Deck::Custom(vec![
+            "Small".to_string(),
+            "Big".to_string(),
+            "∞".to_string(),
+        ]);
+        assert_eq!(validate_deck(deck.clone()), Ok(deck.clone()));
+        assert!(deck.contains("∞"));
+        assert_eq!(deck.cards().len(), 3);
+
+        let cards = |cards: &[&str]| Deck::Custom(cards.iter().map(|c| c.to_string()).collect());
+        assert_eq!(validate_deck(cards(&[" S ", "M"])), Ok(cards(&["S", "M"])));
+        assert_eq!(validate_deck(cards(&[])), Err(ValidationError::InvalidDeck));
+        assert_eq!(
+            validate_deck(cards(&["1", "1 "])),
+            Err(ValidationError::InvalidDeck)
+        );
+        assert_eq!(
+            validate_deck(cards(&["1", " "])),
+            Err(ValidationError::Empty { field: Field::Card })
+        );
+        assert_eq!(
+            validate_deck(cards(&["1", "Far too long a card"])),
+            Err(ValidationError::TooLong {
+                field: Field::Card,
+                max_chars: MAX_CARD_CHARS
+            })
+        );
+        assert_eq!(
+            validate_deck(Deck::Custom(vec!["1".to_string(); MAX_DECK_CARDS + 1])),
+            Err(ValidationError::TooManyCards {
+                max: MAX_DECK_CARDS
+            })
+        );
+    }
+
+    #[test]
+    fn clear_points_without_votes_records_nothing() {
+        let mut session = Session::new(
+            "Planning".to_string(),
+            Duration::from_secs(1800),
+            Deck::default(),
+        );
+        session.clear_points();
+        assert!(session.history().is_empty());
+    }
+
+    #[test]
+    fn server_messages_keep_the_session_message_format() {
+        let update = SessionMessage::SessionEnded(SessionEndReason::Expired);
+        let json = serde_json::to_string(&ServerMessage::Session(update.clone())).unwrap();
+        assert_eq!(json, serde_json::to_string(&update).unwrap());
+        assert!(matches!(
+            serde_json::from_str::<ServerMessage>(&json).unwrap(),
+            ServerMessage::Session(SessionMessage::SessionEnded(SessionEndReason::Expired))
+        ));
+
+        let error = r#"{"Error":{"code":"not_facilitator","message":"Only the facilitator can do that","request_id":null}}"#;
+        assert!(matches!(
+            serde_json::from_str::<ServerMessage>(error).unwrap(),
+            ServerMessage::Error {
+                code: ErrorCode::NotFacilitator,
+                request_id: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn client_messages_accept_requests_and_bare_messages() {
+        let request = r#"{"Request":{"request_id":"r1","message":"ClearPoints"}}"#;
+        assert!(matches!(
+            serde_json::from_str::<ClientMessage>(request).unwrap(),
+            ClientMessage::Request {
+                message: SessionMessage::ClearPoints,
+                ..
+            }
+        ));
+
+        let bare = serde_json::to_string(&SessionMessage::SetTopic("Login".into())).unwrap();
+        assert!(matches!(
+            serde_json::from_str::<ClientMessage>(&bare).unwrap(),
+            ClientMessage::Session(SessionMessage::SetTopic(_))
+        ));
+    }
+
+    #[test]
+    fn request_ids_are_read_without_the_message() {
+        let request = r#"{"Request":{"request_id":"r1","message":{"NoSuchMessage":1}}}"#;
+        assert_eq!(
+            ClientMessage::peek_request_id(request),
+            Some("r1".to_string())
+        );
+
+        assert_eq!(ClientMessage::peek_request_id(r#""ClearPoints""#), None);
+        assert_eq!(ClientMessage::peek_request_id("not json"), None);
+    }
 }
@@ -1,5 +1,7 @@
 //! Shared error types and utilities for the kiko project.
 
+use crate::data::{ErrorCode, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, ValidationError};
+
 #[cfg(not(target_arch = "wasm32"))]
 pub use color_eyre::Report;
 
@@ -31,4 +33,55 @@ pub enum WebSocketError {
     ChannelClosed,
     #[error("Not subscribed to any session")]
     NotSubscribed,
+    #[error("Card {0} is not in this session's deck")]
+    InvalidCard(String),
+    #[error("Only the facilitator can do that")]
+    NotFacilitator,
+    #[error("You need to join the session first")]
+    NotJoined,
+    #[error("This connection has already joined the session")]
+    AlreadyJoined,
+    #[error("You can only do that for yourself")]
+    NotYourself,
+    #[error("Resume token is invalid or has expired")]
+    ResumeRejected,
+    #[error(
+        "Protocol version {0} is not supported, this server accepts versions {MIN_PROTOCOL_VERSION} to {PROTOCOL_VERSION}"
+    )]
+    IncompatibleProtocol(u32),
+    #[error("Expected a Hello message to start the connection")]
+    HandshakeRequired,
+    #[error("Too many messages, slow down")]
+    RateLimited,
+    #[error("{0}")]
+    Invalid(#[from] ValidationError),
+    #[error("Failed to store the session")]
+    StorageFailed,
+}
+
+impl WebSocketError {
+    /// The stable code sent to clients alongside the message.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::SessionNotFound(_) => ErrorCode::SessionNotFound,
+            Self::InvalidMessage(_) => ErrorCode::InvalidMessage,
+            Self::AlreadySubscribed => ErrorCode::AlreadySubscribed,
+            Self::NotSubscribed => ErrorCode::NotSubscribed,
+            Self::InvalidCard(_) => ErrorCode::InvalidCard,
+            Self::NotFacilitator => ErrorCode::NotFacilitator,
+            Self::NotJoined => ErrorCode::NotJoined,
+            Self::AlreadyJoined => ErrorCode::Conflict,
+            Self::NotYourself => ErrorCode::NotYourself,
+            Self::ResumeRejected => ErrorCode::ResumeRejected,
+            Self::IncompatibleProtocol(_) | Self::HandshakeRequired => {
+                ErrorCode::IncompatibleProtocol
+            }
+            Self::RateLimited => ErrorCode::RateLimited,
+            Self::Invalid(_) => ErrorCode::ValidationFailed,
+            Self::SerializationFailed(_)
+            | Self::SendFailed
+            | Self::ChannelClosed
+            | Self::StorageFailed => ErrorCode::Internal,
+        }
+    }
 }
@@ -201,6 +201,12 @@ pub struct StoryMarker;
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VoteMarker;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResumeTokenMarker;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FacilitatorTokenMarker;
+
 /// Type alias for Session IDs
 pub type SessionId = Id<SessionMarker>;
 
@@ -213,6 +219,12 @@ pub type StoryId = Id<StoryMarker>;
 /// Type alias for Vote IDs
 pub type VoteId = Id<VoteMarker>;
 
+/// Type alias for the secret tokens participants use to resume their identity
+pub type ResumeToken = Id<ResumeTokenMarker>;
+
+/// Type alias for the secret tokens that grant a session's facilitator role
+pub type FacilitatorToken = Id<FacilitatorTokenMarker>;
+
 /// Convenience functions for generating common ID types
 impl SessionId {
     /// Generates a new session ID with a user-friendly format (8 characters, mixed case).
@@ -275,6 +287,45 @@ impl VoteId {
     }
 }
 
+/// Generates an unguessable token (20 characters).
+///
+/// Unlike the other IDs, which come from a shared sequence, each chunk of the
+/// token comes from a freshly seeded generator so tokens can't be predicted.
+/// Chunks are kept short because longer ones overflow the generator's state.
+fn generate_secret<T>() -> Id<T> {
+    const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let value = (0..4)
+        .map(|_| Id::<T>::generate_custom(5, ALPHABET).into_string())
+        .collect();
+    Id::from_string(value)
+}
+
+impl ResumeToken {
+    /// Generates a new unguessable resume token (20 characters).
+    pub fn new() -> Self {
+        generate_secret()
+    }
+}
+
+impl Default for ResumeToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FacilitatorToken {
+    /// Generates a new unguessable facilitator token (20 characters).
+    pub fn new() -> Self {
+        generate_secret()
+    }
+}
+
+impl Default for FacilitatorToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,6 +365,14 @@ mod tests {
         assert!(id.as_str().chars().all(|c| "ABCD".contains(c)));
     }
 
+    #[test]
+    fn test_resume_tokens_are_unique() {
+        let tokens: std::collections::HashSet<ResumeToken> =
+            (0..100).map(|_| ResumeToken::new()).collect();
+        assert_eq!(tokens.len(), 100);
+        assert!(tokens.iter().all(|token| token.as_str().len() == 20));
+    }
+
     #[test]
     fn test_type_safety() {
         let session_id = SessionId::new();
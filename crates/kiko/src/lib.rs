@@ -9,6 +9,10 @@ pub mod errors;
 pub mod id;
 pub mod log;
 pub mod macros;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+#[cfg(feature = "schema")]
+pub mod schema;
 
 pub use id::Id;
 pub use serde;
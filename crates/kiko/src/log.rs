@@ -51,6 +51,17 @@ pub fn setup() -> Result<(), LogError> {
 /// If the color_eyre or tracing-subscriber installation fails,
 /// an error will be returned.
 pub fn setup() -> Result<(), LogError> {
+    setup_with_filter(DEFAULT_FILTER)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// The log filter used when `RUST_LOG` is not set.
+pub const DEFAULT_FILTER: &str = "tracing=info,tower_http=debug,kiko_backend=debug";
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Setup the logging system like [`setup`], with a custom default log filter.
+/// `RUST_LOG` still takes precedence over `default_filter` when it is set.
+pub fn setup_with_filter(default_filter: &str) -> Result<(), LogError> {
     use tracing_subscriber::EnvFilter;
 
     // Get / set backtrace
@@ -62,12 +73,7 @@ pub fn setup() -> Result<(), LogError> {
 
     // Get/set the log level
     if std::env::var("RUST_LOG").is_err() {
-        unsafe {
-            std::env::set_var(
-                "RUST_LOG",
-                "tracing=info,tower_http=debug,kiko_backend=debug",
-            )
-        }
+        unsafe { std::env::set_var("RUST_LOG", default_filter) }
     }
     // Setup tracing and tracing-subscriber
     tracing_subscriber::fmt::fmt()
@@ -0,0 +1,40 @@
+//! OpenAPI schemas for types that can't derive them.
+//!
+//! Only built with the `openapi` feature, the backend uses it to document its REST API.
+
+use std::borrow::Cow;
+
+use utoipa::{
+    PartialSchema, ToSchema,
+    openapi::{ObjectBuilder, RefOr, Type, schema::Schema},
+};
+
+use crate::id::Id;
+
+/// IDs are serialized as plain strings, whatever they identify.
+impl<T> PartialSchema for Id<T> {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .description(Some("A short, unique identifier"))
+            .examples(["aB3dE5fG"])
+            .into()
+    }
+}
+
+impl<T> ToSchema for Id<T> {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("Id")
+    }
+}
+
+/// How serde writes a [`std::time::Duration`].
+#[derive(ToSchema)]
+#[schema(as = Duration)]
+#[allow(dead_code)]
+pub struct DurationSchema {
+    /// Whole seconds.
+    secs: u64,
+    /// Nanoseconds on top of the whole seconds.
+    nanos: u32,
+}
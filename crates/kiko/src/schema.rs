@@ -0,0 +1,152 @@
+//! JSON Schemas and TypeScript types for everything in [`crate::data`].
+//!
+//! Only built with the `schema` feature. The `kiko-schema` crate writes both to
+//! the `schema/` directory, so clients outside of Rust, like the Playwright tests,
+//! don't have to write the JSON by hand.
+
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde_json::{Value, json};
+use ts_rs::TS;
+
+use crate::data::*;
+use crate::id::Id;
+
+/// IDs are serialized as plain strings, whatever they identify.
+impl<T> JsonSchema for Id<T> {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("Id")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A short, unique identifier",
+            "examples": ["aB3dE5fG"],
+        })
+    }
+}
+
+impl<T> TS for Id<T> {
+    type WithoutGenerics = Id<ts_rs::Dummy>;
+    type OptionInnerType = Self;
+
+    fn name() -> String {
+        "string".to_string()
+    }
+
+    fn inline() -> String {
+        Self::name()
+    }
+
+    fn inline_flattened() -> String {
+        panic!("IDs cannot be flattened")
+    }
+
+    fn decl() -> String {
+        panic!("IDs cannot be declared")
+    }
+
+    fn decl_concrete() -> String {
+        panic!("IDs cannot be declared")
+    }
+}
+
+/// How serde writes a [`std::time::Duration`].
+#[derive(TS)]
+#[ts(rename = "Duration")]
+#[allow(dead_code)]
+pub struct DurationSchema {
+    /// Whole seconds.
+    #[ts(type = "number")]
+    secs: u64,
+    /// Nanoseconds on top of the whole seconds.
+    nanos: u32,
+}
+
+/// Calls `$visit!(Type)` for every exported type, so the two outputs can't disagree.
+///
+/// New types in [`crate::data`] have to be added by hand, the `kiko-schema` tests fail until they are.
+macro_rules! for_each_type {
+    ($visit:ident) => {
+        $visit!(
+            Participant,
+            Role,
+            Story,
+            Deck,
+            Vote,
+            RoundResult,
+            Session,
+            CreateSession,
+            CreatedSession,
+            SessionSummary,
+            Field,
+            ValidationError,
+            JoinSession,
+            Joined,
+            ResumeSession,
+            SubscribeToSession,
+            AddParticipant,
+            RemoveParticipant,
+            JoinRequest,
+            TopicRequest,
+            VoteRequest,
+            HealthStatus,
+            HealthResponse,
+            UptimeInfo,
+            ServiceInfo,
+            PointSession,
+            ReorderStory,
+            SessionEndReason,
+            SessionMessage,
+            Hello,
+            Welcome,
+            ClientMessage,
+            ErrorCode,
+            ErrorBody,
+            ServerMessage
+        )
+    };
+}
+
+/// A JSON Schema (draft 2020-12) document defining every type under `$defs`.
+pub fn json_schema() -> Value {
+    let mut generator = SchemaGenerator::default();
+    macro_rules! define {
+        ($($ty:ty),*) => {
+            $(generator.subschema_for::<$ty>();)*
+        };
+    }
+    for_each_type!(define);
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Kiko",
+        "description": "The messages and REST API bodies shared by the Kiko frontend and backend",
+        "$defs": generator.take_definitions(true),
+    })
+}
+
+/// TypeScript declarations of every type, as one module.
+pub fn typescript() -> String {
+    let mut declarations = vec![
+        "// Generated from the `kiko::data` types by `cargo run -p kiko-schema`, don't edit by hand.\n"
+            .to_string(),
+    ];
+    macro_rules! declare {
+        ($($ty:ty),*) => {
+            $(declarations.push(declaration::<$ty>());)*
+        };
+    }
+    declare!(DurationSchema);
+    for_each_type!(declare);
+
+    declarations.join("\n")
+}
+
+/// Exports one type, with its doc comment.
+fn declaration<T: TS>() -> String {
+    let docs = T::docs().unwrap_or_default();
+    format!("{docs}export {}\n", T::decl())
+}
@@ -0,0 +1,267 @@
+// Generated from the `kiko::data` types by `cargo run -p kiko-schema`, don't edit by hand.
+
+/**
+ * How serde writes a [`std::time::Duration`].
+ */
+export type Duration = { 
+/**
+ * Whole seconds.
+ */
+secs: number, 
+/**
+ * Nanoseconds on top of the whole seconds.
+ */
+nanos: number, };
+
+/**
+ * Represents a participant in a session.
+ *
+ * A participant has a unique ID and a display name. Participants can join and leave
+ * sessions dynamically, and their information is synchronized across all clients.
+ */
+export type Participant = { id: string, name: string, };
+
+/**
+ * The role a participant holds within a session.
+ *
+ * The facilitator runs the meeting: they manage the topic and story backlog,
+ * reveal and clear points, and remove participants. Everyone else can only vote.
+ */
+export type Role = "Facilitator" | "Participant";
+
+/**
+ * Represents a story waiting to be estimated in a session.
+ *
+ * Stories are kept in an ordered backlog on the session. When the facilitator
+ * advances to the next story, it is taken from the front of the backlog and
+ * becomes the current topic.
+ */
+export type Story = { id: string, title: string, };
+
+/**
+ * The set of cards participants can choose from when estimating.
+ *
+ * Sessions use one of the built-in presets or a custom list of cards. Cards are
+ * plain strings, so non-numeric cards such as `"?"`, `"☕"` or `"∞"` are allowed.
+ */
+export type Deck = "Fibonacci" | "ModifiedFibonacci" | "TShirt" | "PowersOfTwo" | { "Custom": Array<string> };
+
+/**
+ * A single participant's vote in a completed round.
+ */
+export type Vote = { id: string, participant_id: string, participant_name: string, points: string | null, 
+/**
+ * Set when the vote was never revealed and is redacted for this viewer.
+ */
+hidden?: boolean, };
+
+/**
+ * The recorded outcome of a completed estimation round.
+ *
+ * A round is archived into the session history whenever the points are
+ * cleared, so the team can look back at what was agreed on. Timestamps are
+ * seconds since the Unix epoch.
+ */
+export type RoundResult = { topic: string, votes: Array<Vote>, started: number, ended: number, revealed: boolean, };
+
+export type Session = { id: string, name: string, started: number, duration: Duration, members: Array<Participant>, facilitator: string | null, current_topic: string, current_points: { [key in string]?: string | null }, hide_points: boolean, deck: Deck, stories: Array<Story>, round_started: number, 
+/**
+ * Counts the changes stored so far, so an older snapshot can be told from a newer one.
+ */
+version: number, 
+/**
+ * Completed rounds, only kept in storage and served by themselves, see [`Session::history_for`].
+ */
+history?: Array<RoundResult>, 
+/**
+ * Participants whose hidden votes were redacted from this copy of the session.
+ *
+ * Only ever filled in by [`Session::redacted_for`], the stored session keeps every vote.
+ */
+hidden_votes?: Array<string>, };
+
+export type CreateSession = { name: string, duration: Duration, deck: Deck, };
+
+/**
+ * Answer to creating a session over the REST API.
+ *
+ * The facilitator token is a secret, whoever joins with it becomes the facilitator.
+ */
+export type CreatedSession = { session: Session, facilitator_token: string, };
+
+/**
+ * What anyone may see of a session when listing them.
+ *
+ * Leaves out the participants, votes and every secret, see [`Session::summary`].
+ */
+export type SessionSummary = { id: string, name: string, participant_count: number, };
+
+/**
+ * A user-provided field checked by the validation rules.
+ */
+export type Field = "session_name" | "participant_name" | "topic" | "story_title" | "duration" | "deck" | "card" | "participants";
+
+/**
+ * A broken validation rule, shared by the backend and the frontend.
+ *
+ * The backend enforces these rules, the frontend checks them before sending so
+ * users see problems right away.
+ */
+export type ValidationError = { "rule": "empty", field: Field, } | { "rule": "too_long", field: Field, max_chars: number, } | { "rule": "invalid_characters", field: Field, } | { "rule": "invalid_duration", max_secs: number, } | { "rule": "invalid_deck" } | { "rule": "too_many_cards", max: number, } | { "rule": "session_full", max: number, };
+
+/**
+ * Joins a session as a new participant.
+ *
+ * Pass the token from [`CreatedSession`] to join as the facilitator.
+ */
+export type JoinSession = { session_id: string, participant_name: string, facilitator_token?: string | null, };
+
+/**
+ * Sent only to the socket that joined, confirming who it is in the session.
+ *
+ * The resume token is a secret, keep it to reclaim this identity later.
+ */
+export type Joined = { participant_id: string, resume_token: string, };
+
+/**
+ * Reclaims a participant after reconnecting, using the token from [`Joined`].
+ *
+ * Also subscribes the connection to the session, and is answered with [`Joined`] again.
+ */
+export type ResumeSession = { session_id: string, resume_token: string, };
+
+export type SubscribeToSession = { session_id: string, };
+
+export type AddParticipant = { session_id: string, participant_name: string, };
+
+export type RemoveParticipant = { session_id: string, participant_id: string, };
+
+/**
+ * Request body for joining a session over the REST API.
+ *
+ * Answered with [`Joined`], whose resume token authenticates later requests
+ * as this participant in an `Authorization: Bearer` header.
+ */
+export type JoinRequest = { participant_name: string, 
+/**
+ * The token from [`CreatedSession`], to join as the facilitator.
+ */
+facilitator_token?: string | null, };
+
+/**
+ * Request body for setting a session's topic over the REST API.
+ */
+export type TopicRequest = { topic: string, };
+
+/**
+ * Request body for voting over the REST API, `None` withdraws the vote.
+ */
+export type VoteRequest = { points: string | null, };
+
+/**
+ * Health status enumeration.
+ *
+ * Represents the overall health state of the server.
+ */
+export type HealthStatus = "healthy" | "unhealthy" | "dead";
+
+/**
+ * Health check response structure.
+ *
+ * Contains server health information including status, uptime, and service states.
+ * Used by the `/health` endpoint to provide structured health check data.
+ */
+export type HealthResponse = { status: HealthStatus, timestamp: string, started_at: string, uptime: UptimeInfo, services: ServiceInfo, };
+
+/**
+ * Uptime information in both seconds and human-readable format.
+ */
+export type UptimeInfo = { seconds: number, human: string, };
+
+/**
+ * Service status information.
+ */
+export type ServiceInfo = { sessions: string, active_sessions: number, };
+
+/**
+ * Point a given task in the session.
+ */
+export type PointSession = { session_id: string, participant_id: string, points: string | null, };
+
+/**
+ * Move a story to a new position in the session's backlog.
+ */
+export type ReorderStory = { story_id: string, position: number, };
+
+/**
+ * Why a session was ended.
+ */
+export type SessionEndReason = "Expired" | "Abandoned" | "Closed";
+
+/**
+ * WebSocket message types for session operations.
+ *
+ * This enum defines all possible messages that can be sent between clients and the server
+ * for session management operations. Messages are JSON-serialized for WebSocket transport.
+ */
+export type SessionMessage = { "CreateSession": CreateSession } | { "JoinSession": JoinSession } | { "Joined": Joined } | { "ResumeSession": ResumeSession } | { "SubscribeToSession": SubscribeToSession } | { "AddParticipant": AddParticipant } | { "RemoveParticipant": RemoveParticipant } | { "PointSession": PointSession } | { "SetTopic": string } | "ClearPoints" | { "SessionUpdate": Session } | "ToggleHidePoints" | { "AddStory": string } | { "RemoveStory": string } | { "ReorderStory": ReorderStory } | "NextStory" | { "SessionEnded": SessionEndReason };
+
+/**
+ * The first message a client sends on a new WebSocket connection.
+ */
+export type Hello = { protocol_version: number, 
+/**
+ * Identifies the client in the server logs, e.g. `kiko-frontend/0.1.0`
+ */
+client_name: string, };
+
+/**
+ * The server's answer to an accepted [`Hello`].
+ */
+export type Welcome = { 
+/**
+ * The protocol version the server speaks
+ */
+protocol_version: number, 
+/**
+ * The oldest protocol version the server accepts
+ */
+min_protocol_version: number, };
+
+/**
+ * Everything a client sends to the server over the WebSocket.
+ *
+ * Bare session messages are still accepted. Wrapping one in a `Request` makes the
+ * server answer with a [`ServerMessage::Ack`] or [`ServerMessage::Error`] carrying
+ * the same request ID, so the client can tell how that particular message fared.
+ */
+export type ClientMessage = { "Hello": Hello } | { "Request": { request_id: string, message: SessionMessage, } } | SessionMessage;
+
+/**
+ * Stable, machine-readable reason a WebSocket or REST request failed.
+ *
+ * Clients match on these instead of the human-readable message, so a code must
+ * never change its meaning once released.
+ */
+export type ErrorCode = "session_not_found" | "invalid_message" | "already_subscribed" | "not_subscribed" | "invalid_card" | "not_facilitator" | "not_joined" | "not_yourself" | "resume_rejected" | "incompatible_protocol" | "rate_limited" | "validation_failed" | "conflict" | "internal";
+
+/**
+ * The JSON body of every REST API error response.
+ */
+export type ErrorBody = { code: ErrorCode, message: string, 
+/**
+ * Every rule the request broke, for [`ErrorCode::ValidationFailed`] errors.
+ */
+violations?: Array<ValidationError>, };
+
+/**
+ * Everything the server sends to a client over the WebSocket.
+ *
+ * Session messages are sent unwrapped, exactly as a bare [`SessionMessage`] would be,
+ * so only errors carry an envelope of their own.
+ */
+export type ServerMessage = { "Welcome": Welcome } | { "Ack": { request_id: string, } } | { "Error": { code: ErrorCode, message: string, 
+/**
+ * The client's ID for the request that failed, if it sent one.
+ */
+request_id: string | null, } } | SessionMessage;